@@ -0,0 +1,228 @@
+use std::path::Path;
+
+use crate::document::{AltoPage, FontStyle};
+use crate::layout::{group_blocks, group_lines, reading_order};
+
+/// ALTO v4 XML serialization (coordinates in 1/1200 inch, top-left origin)
+pub struct AltoWriter;
+
+impl AltoWriter {
+    const NAMESPACE: &'static str = "http://www.loc.gov/standards/alto/ns-v4#";
+    const SCHEMA_LOCATION: &'static str = "http://www.loc.gov/standards/alto/v4/alto-4-4.xsd";
+
+    /// Serialize a complete ALTO document for one or more pages
+    pub fn write_document(source: &Path, pages: &[AltoPage]) -> String {
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<alto xmlns=\"{ns}\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" xsi:schemaLocation=\"{ns} {schema}\">\n",
+            ns = Self::NAMESPACE,
+            schema = Self::SCHEMA_LOCATION
+        ));
+
+        xml.push_str("  <Description>\n");
+        xml.push_str("    <MeasurementUnit>inch1200</MeasurementUnit>\n");
+        xml.push_str("    <sourceImageInformation>\n");
+        xml.push_str(&format!(
+            "      <fileName>{}</fileName>\n",
+            Self::escape(&source.to_string_lossy())
+        ));
+        xml.push_str("    </sourceImageInformation>\n");
+        xml.push_str("    <Processing ID=\"proc_1\">\n");
+        xml.push_str("      <processingSoftware>\n");
+        xml.push_str("        <softwareName>chonker95</softwareName>\n");
+        xml.push_str(&format!(
+            "        <softwareVersion>{}</softwareVersion>\n",
+            env!("CARGO_PKG_VERSION")
+        ));
+        xml.push_str("      </processingSoftware>\n");
+        xml.push_str("    </Processing>\n");
+        xml.push_str("  </Description>\n");
+
+        // One TextStyle per distinct set of font attributes, referenced from each String
+        let mut styles: Vec<String> = Vec::new();
+        for element in pages.iter().flat_map(|page| &page.elements) {
+            if let Some(style) = &element.style {
+                let attributes = Self::style_attributes(style);
+                if !styles.contains(&attributes) {
+                    styles.push(attributes);
+                }
+            }
+        }
+        if !styles.is_empty() {
+            xml.push_str("  <Styles>\n");
+            for (idx, attributes) in styles.iter().enumerate() {
+                xml.push_str(&format!("    <TextStyle ID=\"ts{}\" {}/>\n", idx, attributes));
+            }
+            xml.push_str("  </Styles>\n");
+        }
+
+        xml.push_str("  <Layout>\n");
+        for page in pages {
+            Self::write_page(&mut xml, page, &styles);
+        }
+        xml.push_str("  </Layout>\n");
+        xml.push_str("</alto>\n");
+        xml
+    }
+
+    fn write_page(xml: &mut String, page: &AltoPage, styles: &[String]) {
+        let page_id = format!("p{}", page.number);
+
+        xml.push_str(&format!(
+            "    <Page ID=\"{}\" PHYSICAL_IMG_NR=\"{}\" WIDTH=\"{}\" HEIGHT=\"{}\">\n",
+            page_id,
+            page.number,
+            Self::coord(page.width),
+            Self::coord(page.height)
+        ));
+        xml.push_str(&format!(
+            "      <PrintSpace HPOS=\"0\" VPOS=\"0\" WIDTH=\"{}\" HEIGHT=\"{}\">\n",
+            Self::coord(page.width),
+            Self::coord(page.height)
+        ));
+
+        for (block_idx, block) in reading_order(group_blocks(group_lines(&page.elements))).iter().enumerate() {
+            let block_id = format!("{}_tb{}", page_id, block_idx);
+
+            xml.push_str(&format!(
+                "        <TextBlock ID=\"{}\" HPOS=\"{}\" VPOS=\"{}\" WIDTH=\"{}\" HEIGHT=\"{}\">\n",
+                block_id, Self::coord(block.hpos), Self::coord(block.vpos), Self::coord(block.width), Self::coord(block.height)
+            ));
+
+            for (line_idx, line) in block.lines.iter().enumerate() {
+                xml.push_str(&format!(
+                    "          <TextLine ID=\"{}_tl{}\" HPOS=\"{}\" VPOS=\"{}\" WIDTH=\"{}\" HEIGHT=\"{}\" BASELINE=\"{}\">\n",
+                    block_id,
+                    line_idx,
+                    Self::coord(line.hpos),
+                    Self::coord(line.vpos),
+                    Self::coord(line.width),
+                    Self::coord(line.height),
+                    Self::coord(line.baseline)
+                ));
+
+                for (word_idx, element) in line.words.iter().enumerate() {
+                    // Explicit whitespace between consecutive words
+                    if word_idx > 0 {
+                        let prev = line.words[word_idx - 1];
+                        let gap_start = prev.hpos + prev.width;
+                        xml.push_str(&format!(
+                            "            <SP HPOS=\"{}\" VPOS=\"{}\" WIDTH=\"{}\"/>\n",
+                            Self::coord(gap_start),
+                            Self::coord(element.vpos),
+                            Self::coord((element.hpos - gap_start).max(0.0))
+                        ));
+                    }
+
+                    let style_ref = element
+                        .style
+                        .as_ref()
+                        .and_then(|style| {
+                            let attributes = Self::style_attributes(style);
+                            styles.iter().position(|s| *s == attributes)
+                        })
+                        .map(|idx| format!(" STYLEREFS=\"ts{}\"", idx))
+                        .unwrap_or_default();
+                    xml.push_str(&format!(
+                        "            <String ID=\"{}_{}\" CONTENT=\"{}\" HPOS=\"{}\" VPOS=\"{}\" WIDTH=\"{}\" HEIGHT=\"{}\"{}/>\n",
+                        page_id,
+                        element.id,
+                        Self::escape(&element.content),
+                        Self::coord(element.hpos),
+                        Self::coord(element.vpos),
+                        Self::coord(element.width),
+                        Self::coord(element.height),
+                        style_ref
+                    ));
+                }
+
+                xml.push_str("          </TextLine>\n");
+            }
+
+            xml.push_str("        </TextBlock>\n");
+        }
+
+        xml.push_str("      </PrintSpace>\n");
+        xml.push_str("    </Page>\n");
+    }
+
+    // TextStyle attributes; FONTSTYLE is a space-separated list and is left out for regular text
+    fn style_attributes(style: &FontStyle) -> String {
+        let (r, g, b) = style.color;
+        let mut attributes = format!(
+            "FONTFAMILY=\"{}\" FONTSIZE=\"{:.1}\" FONTCOLOR=\"{:02X}{:02X}{:02X}\"",
+            Self::escape(&style.family),
+            style.size,
+            r,
+            g,
+            b
+        );
+
+        let flags: Vec<&str> = [(style.bold, "bold"), (style.italic, "italics")]
+            .into_iter()
+            .filter_map(|(set, name)| set.then_some(name))
+            .collect();
+        if !flags.is_empty() {
+            attributes.push_str(&format!(" FONTSTYLE=\"{}\"", flags.join(" ")));
+        }
+        attributes
+    }
+
+    // Elements are measured in PDF points (1/72 inch); ALTO has no unit for those
+    fn coord(value: f32) -> String {
+        format!("{:.0}", value.max(0.0) * 1200.0 / 72.0)
+    }
+
+    fn escape(text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        for ch in text.chars() {
+            match ch {
+                '&' => escaped.push_str("&amp;"),
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                '"' => escaped.push_str("&quot;"),
+                '\'' => escaped.push_str("&apos;"),
+                // Control characters are not allowed in XML 1.0
+                c if (c as u32) < 0x20 && c != '\t' && c != '\n' && c != '\r' => {}
+                c => escaped.push(c),
+            }
+        }
+        escaped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::AltoElement;
+
+    #[test]
+    fn coordinates_are_converted_from_points_to_inch1200() {
+        assert_eq!(AltoWriter::coord(72.0), "1200");
+        assert_eq!(AltoWriter::coord(612.0), "10200");
+        assert_eq!(AltoWriter::coord(0.3), "5");
+        assert_eq!(AltoWriter::coord(-4.0), "0");
+    }
+
+    #[test]
+    fn markup_and_control_characters_are_escaped() {
+        assert_eq!(AltoWriter::escape("a<b> & \"c\" 'd'"), "a&lt;b&gt; &amp; &quot;c&quot; &apos;d&apos;");
+        assert_eq!(AltoWriter::escape("tab\there\u{0}\u{1b}"), "tab\there");
+    }
+
+    #[test]
+    fn document_declares_inch1200_and_scales_every_position() {
+        let page = AltoPage {
+            number: 1,
+            width: 612.0,
+            height: 792.0,
+            elements: vec![AltoElement::new("w_0".to_string(), "R&D".to_string(), 72.0, 144.0, 36.0, 12.0)],
+        };
+        let xml = AltoWriter::write_document(Path::new("report.pdf"), &[page]);
+
+        assert!(xml.contains("<MeasurementUnit>inch1200</MeasurementUnit>"));
+        assert!(xml.contains("WIDTH=\"10200\" HEIGHT=\"13200\""));
+        assert!(xml.contains("CONTENT=\"R&amp;D\" HPOS=\"1200\" VPOS=\"2400\" WIDTH=\"600\" HEIGHT=\"200\""));
+    }
+}
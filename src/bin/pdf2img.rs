@@ -1,6 +1,7 @@
 use anyhow::Result;
-use pdfium_render::prelude::*;
+use chonker95::{Document, Pdfium};
 use std::env;
+use std::path::Path;
 
 fn main() -> Result<()> {
     let args: Vec<String> = env::args().collect();
@@ -14,29 +15,17 @@ fn main() -> Result<()> {
     let page_num: u32 = args[2].parse().unwrap_or(1);
     let output_path = &args[3];
 
-    // Load PDF and extract page
+    // Load PDF and render the page to an image
     let pdfium = Pdfium::default();
-    let document = pdfium.load_pdf_from_file(pdf_path, None)?;
-    let page_index = (page_num - 1) as u16;
+    let document = Document::open(&pdfium, pdf_path)?;
 
-    if page_index >= document.pages().len() {
+    if page_num == 0 || page_num > document.page_count() {
         eprintln!("Page {} not found in PDF", page_num);
         std::process::exit(1);
     }
 
-    let page = document.pages().get(page_index)?;
-
-    // Render page to image
-    let render_config = PdfRenderConfig::new()
-        .set_target_width(800)
-        .set_target_height(1000);
-
-    let bitmap = page.render_with_config(&render_config)?;
-    let image_data = bitmap.as_image();
-
-    // Save as PNG
-    image_data.save(output_path)?;
+    document.save_page_image(page_num, 800, 1000, Path::new(output_path))?;
 
     println!("Converted page {} to {}", page_num, output_path);
     Ok(())
-}
\ No newline at end of file
+}
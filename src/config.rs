@@ -0,0 +1,140 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::files::MacFileManager;
+use crate::layout::LayoutParams;
+
+/// How the editor shows the page: laid out like the PDF (alone or beside it) or reflowed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayMode {
+    TextOnly,
+    SplitScreen,
+    /// Blocks in reading order as wrapped paragraphs; read-only
+    Reflow,
+}
+
+// The file as written, before validation; everything is optional
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    default_mode: Option<DisplayMode>,
+    layout: LayoutFile,
+    colors: HashMap<String, String>,
+    keys: HashMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct LayoutFile {
+    scale: Option<f32>,
+    min_cols: Option<usize>,
+    min_rows: Option<usize>,
+    cell_width: Option<f32>,
+    cell_height: Option<f32>,
+}
+
+/// User settings from `config.toml` in the config directory
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub default_mode: DisplayMode,
+    pub layout: LayoutParams,
+    /// `[colors]` as written; the front-end that draws them interprets it
+    pub colors: HashMap<String, String>,
+    /// `[keys]` as written; the front-end that reads keys interprets it
+    pub keys: HashMap<String, String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            default_mode: DisplayMode::TextOnly,
+            layout: LayoutParams::default(),
+            colors: HashMap::new(),
+            keys: HashMap::new(),
+        }
+    }
+}
+
+impl Config {
+    pub fn path() -> PathBuf {
+        MacFileManager::get_config_dir().join("config.toml")
+    }
+
+    /// Load the config file, or the defaults if there isn't one
+    pub fn load() -> Result<Self> {
+        let path = Self::path();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Self::load_from(&path)
+    }
+
+    /// Load and validate, listing every problem at once rather than stopping at the first
+    pub fn load_from(path: &Path) -> Result<Self> {
+        let (config, errors) = Self::read(path)?;
+        Self::check(path, &errors)?;
+        Ok(config)
+    }
+
+    /// Parse without failing on invalid values: problems come back beside a config that uses
+    /// the defaults in their place, so a front-end can add its own before reporting them
+    pub fn read(path: &Path) -> Result<(Self, Vec<String>)> {
+        let text = std::fs::read_to_string(path)?;
+        let file: ConfigFile = toml::from_str(&text)
+            .map_err(|e| anyhow::anyhow!("Invalid config {}:\n{}", path.display(), e))?;
+
+        let mut errors = Vec::new();
+        let config = Self {
+            default_mode: file.default_mode.unwrap_or(DisplayMode::TextOnly),
+            layout: Self::layout(&file.layout, &mut errors),
+            colors: file.colors,
+            keys: file.keys,
+        };
+        Ok((config, errors))
+    }
+
+    /// Fail with every problem found in the file at `path` listed, if there are any
+    pub fn check(path: &Path, errors: &[String]) -> Result<()> {
+        if !errors.is_empty() {
+            anyhow::bail!("Invalid config {}:\n  - {}", path.display(), errors.join("\n  - "));
+        }
+        Ok(())
+    }
+
+    fn layout(file: &LayoutFile, errors: &mut Vec<String>) -> LayoutParams {
+        let defaults = LayoutParams::default();
+        let mut positive = |name: &str, value: Option<f32>, default: f32, max: f32| match value {
+            Some(v) if v > 0.0 && v <= max => v,
+            Some(v) => {
+                errors.push(format!("layout.{}: {} is out of range (0, {}]", name, v, max));
+                default
+            }
+            None => default,
+        };
+        let scale = positive("scale", file.scale, defaults.scale, 10.0);
+        let cell_width = positive("cell_width", file.cell_width, defaults.cell_width, 100.0);
+        let cell_height = positive("cell_height", file.cell_height, defaults.cell_height, 100.0);
+
+        let mut size = |name: &str, value: Option<usize>, default: usize| match value {
+            Some(v) if (1..=10_000).contains(&v) => v,
+            Some(v) => {
+                errors.push(format!("layout.{}: {} is out of range 1..=10000", name, v));
+                default
+            }
+            None => default,
+        };
+        let min_cols = size("min_cols", file.min_cols, defaults.min_cols);
+        let min_rows = size("min_rows", file.min_rows, defaults.min_rows);
+
+        LayoutParams {
+            scale,
+            min_cols,
+            min_rows,
+            cell_width,
+            cell_height,
+        }
+    }
+}
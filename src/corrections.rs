@@ -0,0 +1,346 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::document::AltoElement;
+use crate::files::MacFileManager;
+
+/// Bumped whenever saved corrections can no longer be matched to words the same way
+const FILE_VERSION: u32 = 2;
+
+/// New text for one extracted word, and the extracted text it was made against
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Correction {
+    pub original: String,
+    pub text: String,
+}
+
+/// User corrections layered over the extracted elements of one page
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PageCorrections {
+    /// Corrected content keyed by element id
+    pub content: HashMap<String, Correction>,
+    /// Extracted text of the elements the user deleted, keyed by id
+    pub deleted: HashMap<String, String>,
+    /// Words typed where no extracted element existed
+    pub inserted: Vec<AltoElement>,
+}
+
+impl PageCorrections {
+    pub fn is_empty(&self) -> bool {
+        self.content.is_empty() && self.deleted.is_empty() && self.inserted.is_empty()
+    }
+
+    /// Derive corrections by comparing edited elements against the extraction
+    pub fn diff(original: &[AltoElement], edited: &[AltoElement]) -> Self {
+        let edited_by_id: HashMap<&str, &AltoElement> = edited
+            .iter()
+            .map(|e| (e.id.as_str(), e))
+            .collect();
+        let original_ids: HashSet<&str> = original.iter().map(|e| e.id.as_str()).collect();
+
+        let mut corrections = Self::default();
+        for element in original {
+            match edited_by_id.get(element.id.as_str()) {
+                Some(edited) if edited.content != element.content => {
+                    corrections.content.insert(
+                        element.id.clone(),
+                        Correction {
+                            original: element.content.clone(),
+                            text: edited.content.clone(),
+                        },
+                    );
+                }
+                Some(_) => {}
+                None => {
+                    corrections.deleted.insert(element.id.clone(), element.content.clone());
+                }
+            }
+        }
+
+        corrections.inserted = edited
+            .iter()
+            .filter(|e| !original_ids.contains(e.id.as_str()))
+            .cloned()
+            .collect();
+
+        corrections
+    }
+
+    /// Rebuild the edited element list from a fresh extraction; corrections whose word
+    /// no longer has the text they were made against are skipped
+    pub fn apply(&self, original: &[AltoElement]) -> Vec<AltoElement> {
+        let mut elements: Vec<AltoElement> = original
+            .iter()
+            .filter(|e| self.deleted.get(&e.id) != Some(&e.content))
+            .map(|e| {
+                let mut element = e.clone();
+                if let Some(correction) = self.content.get(&e.id).filter(|c| c.original == e.content) {
+                    element.content = correction.text.clone();
+                }
+                element
+            })
+            .collect();
+
+        elements.extend(self.inserted.iter().cloned());
+        elements
+    }
+
+    /// How many content corrections and deletions no longer match an extracted word
+    pub fn unmatched(&self, original: &[AltoElement]) -> usize {
+        let by_id: HashMap<&str, &str> = original.iter().map(|e| (e.id.as_str(), e.content.as_str())).collect();
+        let content = self.content.iter().filter(|(id, c)| by_id.get(id.as_str()) != Some(&c.original.as_str()));
+        let deleted = self.deleted.iter().filter(|(id, text)| by_id.get(id.as_str()) != Some(&text.as_str()));
+        content.count() + deleted.count()
+    }
+}
+
+/// A page's corrections and cursor at one point in its edit history
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EditSnapshot {
+    pub corrections: PageCorrections,
+    /// Cursor as a (row, column) position in the page's grid, not on screen
+    pub position: (usize, usize),
+}
+
+/// Undo and redo stacks for one page
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PageHistory {
+    undo: Vec<EditSnapshot>,
+    redo: Vec<EditSnapshot>,
+}
+
+impl PageHistory {
+    const MAX_DEPTH: usize = 200;
+
+    pub fn is_empty(&self) -> bool {
+        self.undo.is_empty() && self.redo.is_empty()
+    }
+
+    /// Remember the state before an edit; a new edit discards anything undone
+    pub fn record(&mut self, before: EditSnapshot) {
+        self.undo.push(before);
+        if self.undo.len() > Self::MAX_DEPTH {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    /// Step back, handing over the current state so it can be redone
+    pub fn undo(&mut self, current: EditSnapshot) -> Option<EditSnapshot> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        Some(previous)
+    }
+
+    pub fn redo(&mut self, current: EditSnapshot) -> Option<EditSnapshot> {
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        Some(next)
+    }
+}
+
+/// Corrections for one document, persisted under the cache directory
+pub struct CorrectionStore {
+    path: PathBuf,
+    pages: HashMap<u32, PageCorrections>,
+    history: HashMap<u32, PageHistory>,
+    /// Where an unreadable or outdated corrections file was moved when the store was opened
+    quarantined: Option<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CorrectionFile {
+    /// Missing in files from before versioning, which count as version 0
+    #[serde(default)]
+    version: u32,
+    source: String,
+    pages: HashMap<u32, PageCorrections>,
+    #[serde(default)]
+    history: HashMap<u32, PageHistory>,
+}
+
+// Just the version, readable from files of any version
+#[derive(Deserialize)]
+struct FileVersion {
+    #[serde(default)]
+    version: u32,
+}
+
+impl CorrectionStore {
+    /// Open the store for a PDF, keyed by a hash of its content
+    pub fn open(pdf_path: &Path) -> Result<Self> {
+        let dir = MacFileManager::get_cache_dir().join("corrections");
+        MacFileManager::ensure_dir_exists(&dir)?;
+
+        let path = dir.join(format!("{}.json", Self::content_hash(pdf_path)?));
+        let mut store = Self {
+            path,
+            pages: HashMap::new(),
+            history: HashMap::new(),
+            quarantined: None,
+        };
+        if !store.path.exists() {
+            return Ok(store);
+        }
+
+        let parsed = std::fs::read_to_string(&store.path)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| Self::parse(&data));
+        match parsed {
+            Ok(file) => {
+                store.pages = file.pages;
+                store.history = file.history;
+            }
+            Err(e) => {
+                // A damaged or outdated file shouldn't stop the PDF from opening; keep it aside for
+                // recovery, under a name of its own so an earlier set-aside file isn't overwritten
+                let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+                let aside = store.path.with_extension(format!("{}.json.bad", stamp));
+                std::fs::rename(&store.path, &aside)?;
+                log::warn!("unusable corrections {} ({}), moved to {}", store.path.display(), e, aside.display());
+                store.quarantined = Some(aside);
+            }
+        }
+        Ok(store)
+    }
+
+    fn parse(data: &str) -> Result<CorrectionFile> {
+        // Older files matched corrections to words differently; applying them would edit the wrong words
+        let version = serde_json::from_str::<FileVersion>(data)?.version;
+        if version != FILE_VERSION {
+            anyhow::bail!("file version {} is not the supported version {}", version, FILE_VERSION);
+        }
+        Ok(serde_json::from_str(data)?)
+    }
+
+    fn content_hash(pdf_path: &Path) -> Result<String> {
+        // Hash in chunks so large PDFs aren't read into memory whole
+        let mut file = File::open(pdf_path)?;
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0; 64 * 1024];
+        loop {
+            let read = file.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+        }
+        Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+    }
+
+    /// Where an unreadable or outdated corrections file was moved aside, if opening found one
+    pub fn quarantined(&self) -> Option<&Path> {
+        self.quarantined.as_deref()
+    }
+
+    pub fn get(&self, page: u32) -> Option<&PageCorrections> {
+        self.pages.get(&page)
+    }
+
+    pub fn set(&mut self, page: u32, corrections: PageCorrections) {
+        if corrections.is_empty() {
+            self.pages.remove(&page);
+        } else {
+            self.pages.insert(page, corrections);
+        }
+    }
+
+    pub fn history_mut(&mut self, page: u32) -> &mut PageHistory {
+        self.history.entry(page).or_default()
+    }
+
+    pub fn save(&self, pdf_path: &Path) -> Result<()> {
+        // Don't leave empty files behind for documents that were never edited
+        if self.pages.is_empty() && self.history.values().all(PageHistory::is_empty) {
+            if self.path.exists() {
+                std::fs::remove_file(&self.path)?;
+            }
+            return Ok(());
+        }
+
+        let file = CorrectionFile {
+            version: FILE_VERSION,
+            source: pdf_path.to_string_lossy().into_owned(),
+            pages: self.pages.clone(),
+            history: self
+                .history
+                .iter()
+                .filter(|(_, history)| !history.is_empty())
+                .map(|(page, history)| (*page, history.clone()))
+                .collect(),
+        };
+
+        // Write beside the file and rename over it, so an interrupted save can't leave it half written
+        let temp = self.path.with_extension("json.tmp");
+        std::fs::write(&temp, serde_json::to_string_pretty(&file)?)?;
+        std::fs::rename(&temp, &self.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(id: &str, content: &str) -> AltoElement {
+        AltoElement::new(id.to_string(), content.to_string(), 0.0, 0.0, 20.0, 10.0)
+    }
+
+    #[test]
+    fn diff_records_changed_deleted_and_inserted_words() {
+        let original = vec![word("w_0", "Teh"), word("w_1", "quick"), word("w_2", "fox")];
+        let edited = vec![word("w_0", "The"), word("w_2", "fox"), word("ins_0", "brown")];
+
+        let corrections = PageCorrections::diff(&original, &edited);
+        assert_eq!(
+            corrections.content.get("w_0"),
+            Some(&Correction { original: "Teh".to_string(), text: "The".to_string() })
+        );
+        assert_eq!(corrections.content.len(), 1);
+        assert_eq!(corrections.deleted.get("w_1").map(String::as_str), Some("quick"));
+        assert_eq!(corrections.inserted.len(), 1);
+        assert_eq!(corrections.inserted[0].id, "ins_0");
+
+        let contents: Vec<String> = corrections.apply(&original).into_iter().map(|e| e.content).collect();
+        assert_eq!(contents, ["The", "fox", "brown"]);
+        assert_eq!(corrections.unmatched(&original), 0);
+    }
+
+    #[test]
+    fn corrections_whose_word_text_changed_are_skipped() {
+        let original = vec![word("w_0", "Teh"), word("w_1", "quick")];
+        let edited = vec![word("w_0", "The")];
+        let corrections = PageCorrections::diff(&original, &edited);
+
+        // The same ids now name different words, e.g. after the extraction changed
+        let reextracted = vec![word("w_0", "A"), word("w_1", "slow")];
+        let contents: Vec<String> = corrections.apply(&reextracted).into_iter().map(|e| e.content).collect();
+        assert_eq!(contents, ["A", "slow"]);
+        assert_eq!(corrections.unmatched(&reextracted), 2);
+    }
+
+    #[test]
+    fn only_files_of_the_current_version_are_read() {
+        let current = format!(r#"{{"version": {}, "source": "a.pdf", "pages": {{}}}}"#, FILE_VERSION);
+        assert!(CorrectionStore::parse(&current).is_ok());
+
+        // Unversioned files predate matching corrections by their original text
+        let unversioned = r#"{"source": "a.pdf", "pages": {"1": {"content": {"w_0": "The"}, "deleted": [], "inserted": []}}}"#;
+        assert!(CorrectionStore::parse(unversioned).is_err());
+        let newer = format!(r#"{{"version": {}, "source": "a.pdf", "pages": {{}}}}"#, FILE_VERSION + 1);
+        assert!(CorrectionStore::parse(&newer).is_err());
+        assert!(CorrectionStore::parse("{ not json").is_err());
+    }
+
+    #[test]
+    fn unchanged_pages_have_no_corrections() {
+        let original = vec![word("w_0", "The"), word("w_1", "fox")];
+        assert!(PageCorrections::diff(&original, &original).is_empty());
+    }
+}
@@ -0,0 +1,473 @@
+use anyhow::Result;
+use pdfium_render::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::layout::LayoutParams;
+
+/// A word on a page, positioned in PDF points from the top-left corner
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AltoElement {
+    pub id: String,
+    pub content: String,
+    pub hpos: f32,
+    pub vpos: f32,
+    pub width: f32,
+    pub height: f32,
+    // Screen position (calculated from PDF coordinates)
+    pub screen_x: u16,
+    pub screen_y: u16,
+    /// Baseline in PDF points from the top, when extraction knows it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<f32>,
+    /// Typography of the word, when extraction could read it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub style: Option<FontStyle>,
+}
+
+/// Font attributes of a word, taken from its first character
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FontStyle {
+    pub family: String,
+    /// Size in PDF points after the text matrix is applied
+    pub size: f32,
+    pub bold: bool,
+    pub italic: bool,
+    /// Fill color as RGB
+    pub color: (u8, u8, u8),
+}
+
+impl FontStyle {
+    // Subset fonts are named like "ABCDEF+Helvetica-Bold"; the flags alone often miss bold and italic
+    fn from_char(ch: &PdfPageTextChar) -> Self {
+        let name = ch.font_name();
+        let family = match name.split_once('+') {
+            Some((prefix, rest)) if prefix.len() == 6 && prefix.chars().all(|c| c.is_ascii_uppercase()) => rest,
+            _ => name.as_str(),
+        }
+        .to_string();
+        let lower = family.to_lowercase();
+
+        let heavy = matches!(
+            ch.font_weight(),
+            Some(PdfFontWeight::Weight600 | PdfFontWeight::Weight700Bold | PdfFontWeight::Weight800 | PdfFontWeight::Weight900)
+        ) || matches!(ch.font_weight(), Some(PdfFontWeight::Custom(weight)) if weight >= 600);
+        let bold = heavy
+            || ch.font_is_bold_reenforced()
+            || ["bold", "black", "heavy", "semibold", "demi"].iter().any(|w| lower.contains(w));
+        let italic = ch.font_is_italic() || lower.contains("italic") || lower.contains("oblique");
+        let color = ch
+            .fill_color()
+            .map(|c| (c.red(), c.green(), c.blue()))
+            .unwrap_or((0, 0, 0));
+
+        Self {
+            family,
+            size: ch.scaled_font_size().value,
+            bold,
+            italic,
+            color,
+        }
+    }
+}
+
+impl AltoElement {
+    pub fn new(id: String, content: String, hpos: f32, vpos: f32, width: f32, height: f32) -> Self {
+        let defaults = LayoutParams::default();
+        let mut element = Self {
+            id,
+            content,
+            hpos,
+            vpos,
+            width,
+            height,
+            screen_x: 0,
+            screen_y: 0,
+            baseline: None,
+            style: None,
+        };
+        element.place_on_screen(defaults.cell_width, defaults.cell_height);
+        element
+    }
+
+    /// Where the word sits on its line; the bottom of its box if the baseline is unknown
+    pub fn baseline(&self) -> f32 {
+        self.baseline.unwrap_or(self.vpos + self.height)
+    }
+
+    /// Set larger than the page's body text, so likely part of a heading
+    pub fn is_heading(&self, body_size: f32) -> bool {
+        self.style.as_ref().is_some_and(|style| style.size >= body_size * HEADING_RATIO)
+    }
+
+    /// Convert PDF coords to terminal coords for a cell of the given size in points
+    pub fn place_on_screen(&mut self, cell_width: f32, cell_height: f32) {
+        self.screen_x = (self.hpos / cell_width) as u16;
+        self.screen_y = (self.vpos / cell_height) as u16;
+    }
+}
+
+// How much larger than body text a word must be to count as a heading
+const HEADING_RATIO: f32 = 1.2;
+
+/// The font size most of the page's text is set in, weighted by characters
+pub fn body_font_size(elements: &[AltoElement]) -> Option<f32> {
+    // Sizes are bucketed to half points so tiny matrix differences don't split the vote
+    let mut counts: Vec<(i32, usize)> = Vec::new();
+    for element in elements {
+        let Some(style) = &element.style else {
+            continue;
+        };
+        let bucket = (style.size * 2.0).round() as i32;
+        let chars = element.content.chars().count();
+        match counts.iter_mut().find(|(size, _)| *size == bucket) {
+            Some((_, count)) => *count += chars,
+            None => counts.push((bucket, chars)),
+        }
+    }
+
+    counts
+        .into_iter()
+        .max_by_key(|(size, count)| (*count, -size))
+        .map(|(size, _)| size as f32 / 2.0)
+}
+
+/// One extracted page with the dimensions needed for ALTO output
+#[derive(Debug, Clone, Serialize)]
+pub struct AltoPage {
+    pub number: u32,
+    pub width: f32,
+    pub height: f32,
+    pub elements: Vec<AltoElement>,
+}
+
+/// A phrase found by PDFium's text search, positioned like an element
+#[derive(Debug, Clone, Serialize)]
+pub struct TextHit {
+    pub page: u32,
+    /// The line of the page the hit sits on
+    pub context: String,
+    pub hpos: f32,
+    pub vpos: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// An open PDF whose pages can be extracted into elements
+pub struct Document<'a> {
+    path: PathBuf,
+    pdf: PdfDocument<'a>,
+    layout: LayoutParams,
+}
+
+impl<'a> Document<'a> {
+    pub fn open(pdfium: &'a Pdfium, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let pdf = pdfium.load_pdf_from_file(&path, None)?;
+        log::info!("opened {} ({} pages)", path.display(), pdf.pages().len());
+        Ok(Self {
+            path,
+            pdf,
+            layout: LayoutParams::default(),
+        })
+    }
+
+    /// Use these cell metrics for screen positions and fallback extraction
+    pub fn with_layout(mut self, layout: LayoutParams) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn page_count(&self) -> u32 {
+        self.pdf.pages().len() as u32
+    }
+
+    /// Extract a single 1-based page
+    pub fn page(&self, number: u32) -> Result<AltoPage> {
+        let started = Instant::now();
+        let page = self.pdf_page(number)?;
+        let elements = extract_page_elements(&page, &self.layout)?;
+        log::debug!("extracted page {} ({} elements) in {:?}", number, elements.len(), started.elapsed());
+
+        Ok(AltoPage {
+            number,
+            width: page.width().value,
+            height: page.height().value,
+            elements,
+        })
+    }
+
+    /// Extract every page of the document
+    pub fn pages(&self) -> Result<Vec<AltoPage>> {
+        (1..=self.page_count()).map(|number| self.page(number)).collect()
+    }
+
+    /// The page's label from the PDF (e.g. "iv" in a front matter), if it has one
+    pub fn page_label(&self, number: u32) -> Option<String> {
+        let page = self.pdf_page(number).ok()?;
+        page.label().map(str::to_string)
+    }
+
+    /// Find a literal phrase on every page with PDFium's own text search, without laying pages out
+    pub fn search(&self, phrase: &str, match_case: bool, whole_word: bool) -> Result<Vec<TextHit>> {
+        let options = PdfSearchOptions::new()
+            .match_case(match_case)
+            .match_whole_word(whole_word);
+
+        let started = Instant::now();
+        let mut hits = Vec::new();
+        for number in 1..=self.page_count() {
+            let page = self.pdf_page(number)?;
+            let page_width = page.width().value;
+            let page_height = page.height().value;
+            let text = page.text()?;
+            let search = text.search(phrase, &options)?;
+
+            for result in search.iter(PdfSearchDirection::SearchForward) {
+                // A hit that wraps across lines comes back as several segments
+                let (mut left, mut bottom, mut right, mut top) = (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+                for segment in result.iter() {
+                    let bounds = segment.bounds();
+                    left = left.min(bounds.left().value);
+                    bottom = bottom.min(bounds.bottom().value);
+                    right = right.max(bounds.right().value);
+                    top = top.max(bounds.top().value);
+                }
+                if left > right {
+                    continue;
+                }
+
+                let line = PdfRect::new_from_values(bottom, 0.0, top, page_width);
+                let context = text.inside_rect(line).split_whitespace().collect::<Vec<_>>().join(" ");
+                hits.push(TextHit {
+                    page: number,
+                    context,
+                    hpos: left,
+                    vpos: page_height - top, // Flip Y coordinate
+                    width: right - left,
+                    height: top - bottom,
+                });
+            }
+        }
+
+        log::debug!("searched {} pages for {:?}: {} hits in {:?}", self.page_count(), phrase, hits.len(), started.elapsed());
+        Ok(hits)
+    }
+
+    /// Render a 1-based page to an image file, format chosen by extension
+    pub fn save_page_image(&self, number: u32, width: i32, height: i32, output: &Path) -> Result<()> {
+        let render_config = PdfRenderConfig::new()
+            .set_target_width(width)
+            .set_target_height(height);
+
+        let page = self.pdf_page(number)?;
+        let bitmap = page.render_with_config(&render_config)?;
+        bitmap.as_image().save(output)?;
+        Ok(())
+    }
+
+    fn pdf_page(&self, number: u32) -> Result<PdfPage<'_>> {
+        if number == 0 || number > self.page_count() {
+            anyhow::bail!("Page {} not found in PDF", number);
+        }
+        Ok(self.pdf.pages().get((number - 1) as u16)?)
+    }
+}
+
+// Build word elements from a single PDFium page
+fn extract_page_elements(page: &PdfPage, layout: &LayoutParams) -> Result<Vec<AltoElement>> {
+    let text_page = page.text()?;
+    let page_height = page.height().value;
+
+    // Build words from PDFium's per-character boxes rather than guessing widths
+    let mut elements = Vec::new();
+    let mut word = WordBuilder::default();
+
+    for ch in text_page.chars().iter() {
+        let Some(c) = ch.unicode_char() else {
+            continue;
+        };
+
+        // Whitespace (including spaces PDFium inferred) ends the word
+        if c.is_whitespace() || ch.is_generated().unwrap_or(false) {
+            word.finish(&mut elements, page_height);
+            continue;
+        }
+
+        let Ok(bounds) = ch.loose_bounds() else {
+            continue;
+        };
+        let glyph = GlyphBox {
+            left: bounds.left().value,
+            right: bounds.right().value,
+            bottom: bounds.bottom().value,
+            top: bounds.top().value,
+        };
+
+        // Words in some PDFs have no space glyphs, only a gap or a jump to another line
+        if !word.continues_with(&glyph) {
+            word.finish(&mut elements, page_height);
+        }
+        if word.style.is_none() {
+            word.style = Some(FontStyle::from_char(&ch));
+            word.baseline = ch.origin_y().ok().map(|y| page_height - y.value);
+        }
+        word.push(c, glyph);
+    }
+    word.finish(&mut elements, page_height);
+
+    // Fallback if character extraction fails
+    if elements.is_empty() {
+        let text_content = page.text()?.all();
+        let mut y_pos = 0.0;
+
+        for line in text_content.lines() {
+            let mut x_pos = 0.0;
+            for word in line.split_whitespace() {
+                if !word.is_empty() {
+                    elements.push(AltoElement::new(
+                        format!("word_{}", elements.len()),
+                        word.to_string(),
+                        x_pos * layout.cell_width,
+                        y_pos * layout.cell_height,
+                        word.len() as f32 * layout.cell_width,
+                        layout.cell_height,
+                    ));
+                    x_pos += word.len() as f32 + 1.0;
+                }
+            }
+            y_pos += 1.0;
+        }
+    }
+
+    for element in &mut elements {
+        element.place_on_screen(layout.cell_width, layout.cell_height);
+    }
+    Ok(elements)
+}
+
+// A character's box in PDF points, bottom-left origin
+#[derive(Debug, Clone, Copy)]
+struct GlyphBox {
+    left: f32,
+    right: f32,
+    bottom: f32,
+    top: f32,
+}
+
+// Characters collected into the word being built, the union of their boxes and the first one's font and baseline
+#[derive(Default)]
+struct WordBuilder {
+    text: String,
+    bounds: Option<GlyphBox>,
+    last: Option<GlyphBox>,
+    style: Option<FontStyle>,
+    baseline: Option<f32>,
+}
+
+impl WordBuilder {
+    fn push(&mut self, c: char, glyph: GlyphBox) {
+        self.text.push(c);
+        self.bounds = Some(match self.bounds {
+            Some(b) => GlyphBox {
+                left: b.left.min(glyph.left),
+                right: b.right.max(glyph.right),
+                bottom: b.bottom.min(glyph.bottom),
+                top: b.top.max(glyph.top),
+            },
+            None => glyph,
+        });
+        self.last = Some(glyph);
+    }
+
+    // Same line and no more than a fraction of the font height away from the previous glyph
+    fn continues_with(&self, glyph: &GlyphBox) -> bool {
+        let Some(last) = self.last else {
+            return true;
+        };
+        let height = (last.top - last.bottom).max(1.0);
+        let same_line = ((last.top + last.bottom) / 2.0 - (glyph.top + glyph.bottom) / 2.0).abs() < height / 2.0;
+        let gap = glyph.left - last.right;
+        same_line && gap > -height / 2.0 && gap < height * 0.25
+    }
+
+    fn finish(&mut self, elements: &mut Vec<AltoElement>, page_height: f32) {
+        let style = self.style.take();
+        let baseline = self.baseline.take();
+        if let Some(b) = self.bounds.take() {
+            let mut element = AltoElement::new(
+                format!("w_{}", elements.len()),
+                std::mem::take(&mut self.text),
+                b.left,
+                page_height - b.top, // Flip Y coordinate
+                b.right - b.left,
+                b.top - b.bottom,
+            );
+            element.style = style;
+            element.baseline = baseline;
+            elements.push(element);
+        }
+        self.text.clear();
+        self.last = None;
+    }
+}
+
+/// Parse a page list like "1-3,7,10-" into sorted, 1-based page numbers
+pub fn parse_page_ranges(spec: &str, page_count: u32) -> Result<Vec<u32>> {
+    let mut pages = Vec::new();
+
+    for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (start, end) = match part.split_once('-') {
+            Some((start, end)) => {
+                let start = if start.trim().is_empty() { 1 } else { start.trim().parse()? };
+                let end = if end.trim().is_empty() { page_count } else { end.trim().parse()? };
+                (start, end)
+            }
+            None => {
+                let page = part.parse()?;
+                (page, page)
+            }
+        };
+
+        if start == 0 || start > end || end > page_count {
+            anyhow::bail!("Invalid page range '{}' for a {}-page document", part, page_count);
+        }
+        pages.extend(start..=end);
+    }
+
+    // An empty selection would export nothing and still look like success
+    if pages.is_empty() {
+        anyhow::bail!("No pages selected by '{}'", spec);
+    }
+
+    pages.sort_unstable();
+    pages.dedup();
+    Ok(pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_ranges_are_sorted_deduplicated_and_open_ended() {
+        assert_eq!(parse_page_ranges("7, 1-3,2", 10).unwrap(), vec![1, 2, 3, 7]);
+        assert_eq!(parse_page_ranges("9-", 10).unwrap(), vec![9, 10]);
+        assert_eq!(parse_page_ranges("-2", 10).unwrap(), vec![1, 2]);
+    }
+
+    #[test]
+    fn page_ranges_outside_the_document_or_selecting_nothing_are_rejected() {
+        assert!(parse_page_ranges("0", 10).is_err());
+        assert!(parse_page_ranges("5-3", 10).is_err());
+        assert!(parse_page_ranges("11", 10).is_err());
+        assert!(parse_page_ranges("x", 10).is_err());
+        assert!(parse_page_ranges("", 10).is_err());
+        assert!(parse_page_ranges(" , ", 10).is_err());
+    }
+}
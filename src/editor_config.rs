@@ -0,0 +1,306 @@
+//! Key bindings and colors for the terminal editor, read from the `[keys]` and `[colors]`
+//! tables of the shared config file
+
+use anyhow::Result;
+use chonker95::{Config, DisplayMode, LayoutParams};
+use crossterm::event::{KeyCode, KeyModifiers};
+use crossterm::style::Color;
+use std::collections::HashMap;
+
+/// Editor commands that can be bound to keys; cursor movement and typing are not remappable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Save,
+    ExportPage,
+    ExportDocument,
+    SaveSession,
+    Close,
+    Quit,
+    SelectAll,
+    Copy,
+    CopyTsv,
+    Cut,
+    Paste,
+    Undo,
+    Redo,
+    Find,
+    Replace,
+    GoToPage,
+    FirstPage,
+    LastPage,
+    PreviousPage,
+    NextPage,
+    TogglePdf,
+    ToggleReflow,
+    Messages,
+}
+
+// Config names and default bindings; "cmd" is Cmd on macOS and Ctrl elsewhere
+const ACTIONS: &[(Action, &str, &str)] = &[
+    (Action::Save, "save", "cmd+s"),
+    (Action::ExportPage, "export_page", "cmd+e"),
+    (Action::ExportDocument, "export_document", "cmd+shift+e"),
+    (Action::SaveSession, "save_session", "cmd+o"),
+    (Action::Close, "close", "cmd+w"),
+    (Action::Quit, "quit", "cmd+q"),
+    (Action::SelectAll, "select_all", "cmd+a"),
+    (Action::Copy, "copy", "cmd+c"),
+    (Action::CopyTsv, "copy_tsv", "cmd+shift+c"),
+    (Action::Cut, "cut", "cmd+x"),
+    (Action::Paste, "paste", "cmd+v"),
+    (Action::Undo, "undo", "cmd+z"),
+    (Action::Redo, "redo", "cmd+shift+z, cmd+y"),
+    (Action::Find, "find", "ctrl+f"),
+    (Action::Replace, "replace", "ctrl+r"),
+    (Action::GoToPage, "go_to_page", "ctrl+g"),
+    (Action::FirstPage, "first_page", "ctrl+home"),
+    (Action::LastPage, "last_page", "ctrl+end"),
+    (Action::PreviousPage, "previous_page", "ctrl+left"),
+    (Action::NextPage, "next_page", "ctrl+right"),
+    (Action::TogglePdf, "toggle_pdf", "ctrl+p"),
+    (Action::ToggleReflow, "toggle_reflow", "ctrl+t"),
+    (Action::Messages, "messages", "alt+m"),
+];
+
+/// A key with the exact modifiers that must be held
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    const MODIFIERS: KeyModifiers = KeyModifiers::CONTROL
+        .union(KeyModifiers::ALT)
+        .union(KeyModifiers::SHIFT)
+        .union(KeyModifiers::SUPER);
+
+    /// Parse e.g. "ctrl+shift+e", "alt+m" or "f2"
+    pub fn parse(spec: &str) -> Result<Self> {
+        let parts: Vec<&str> = spec.split('+').map(str::trim).collect();
+        let (key, modifier_names) = parts.split_last().filter(|(key, _)| !key.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("empty key binding"))?;
+
+        let mut modifiers = KeyModifiers::NONE;
+        for name in modifier_names {
+            modifiers |= match name.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "option" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                "super" => KeyModifiers::SUPER,
+                "cmd" | "command" => Self::platform_command(),
+                other => anyhow::bail!("unknown modifier '{}' in '{}'", other, spec),
+            };
+        }
+
+        let code = match key.to_lowercase().as_str() {
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "tab" => KeyCode::Tab,
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "backspace" => KeyCode::Backspace,
+            "delete" => KeyCode::Delete,
+            "space" => KeyCode::Char(' '),
+            name if name.len() > 1 && name.starts_with('f') => match name[1..].parse() {
+                Ok(n @ 1..=12) => KeyCode::F(n),
+                _ => anyhow::bail!("unknown key '{}' in '{}'", key, spec),
+            },
+            name if name.chars().count() == 1 => KeyCode::Char(name.chars().next().unwrap()),
+            _ => anyhow::bail!("unknown key '{}' in '{}'", key, spec),
+        };
+
+        Ok(Self { code, modifiers })
+    }
+
+    // Mac users expect Cmd key, others use Ctrl
+    fn platform_command() -> KeyModifiers {
+        if cfg!(target_os = "macos") {
+            KeyModifiers::SUPER
+        } else {
+            KeyModifiers::CONTROL
+        }
+    }
+
+    /// Whether a key event is this binding; an uppercase letter counts as Shift + the letter
+    pub fn matches(&self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        let mut modifiers = modifiers & Self::MODIFIERS;
+        let code = match code {
+            KeyCode::Char(c) if c.is_uppercase() => {
+                modifiers |= KeyModifiers::SHIFT;
+                KeyCode::Char(c.to_lowercase().next().unwrap_or(c))
+            }
+            other => other,
+        };
+        code == self.code && modifiers == self.modifiers
+    }
+}
+
+/// Key bindings for every action
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(KeyBinding, Action)>,
+}
+
+impl Keymap {
+    pub fn action_for(&self, code: KeyCode, modifiers: KeyModifiers) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(binding, _)| binding.matches(code, modifiers))
+            .map(|(_, action)| *action)
+    }
+
+    // Defaults overridden per action; several bindings are separated by commas
+    fn build(overrides: &HashMap<String, String>, errors: &mut Vec<String>) -> Self {
+        for name in overrides.keys() {
+            if !ACTIONS.iter().any(|(_, known, _)| known == name) {
+                errors.push(format!("keys.{}: unknown action", name));
+            }
+        }
+
+        let mut bindings: Vec<(KeyBinding, Action)> = Vec::new();
+        for (action, name, default) in ACTIONS {
+            let spec = overrides.get(*name).map(String::as_str).unwrap_or(default);
+            for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+                match KeyBinding::parse(part) {
+                    Ok(binding) => {
+                        if let Some((_, taken_by)) = bindings.iter().find(|(b, _)| *b == binding) {
+                            let other = ACTIONS.iter().find(|(a, _, _)| a == taken_by).map_or("?", |(_, n, _)| *n);
+                            errors.push(format!("keys.{}: '{}' is already bound to {}", name, part, other));
+                        } else {
+                            bindings.push((binding, *action));
+                        }
+                    }
+                    Err(e) => errors.push(format!("keys.{}: {}", name, e)),
+                }
+            }
+        }
+
+        Self { bindings }
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::build(&HashMap::new(), &mut Vec::new())
+    }
+}
+
+/// Colors for the editor's chrome and emphasized text
+#[derive(Debug, Clone, Copy)]
+pub struct Colors {
+    pub status: Color,
+    /// Words set noticeably larger than the page's body text
+    pub heading: Color,
+    pub prompt: Color,
+    pub selection_fg: Color,
+    pub selection_bg: Color,
+    pub match_bg: Color,
+    pub current_match_bg: Color,
+}
+
+impl Default for Colors {
+    fn default() -> Self {
+        Self {
+            status: Color::Yellow,
+            heading: Color::Green,
+            prompt: Color::Cyan,
+            selection_fg: Color::White,
+            selection_bg: Color::Blue,
+            match_bg: Color::DarkYellow,
+            current_match_bg: Color::Magenta,
+        }
+    }
+}
+
+impl Colors {
+    fn build(file: &HashMap<String, String>, errors: &mut Vec<String>) -> Self {
+        let mut colors = Self::default();
+        for (name, value) in file {
+            let slot = match name.as_str() {
+                "status" => &mut colors.status,
+                "heading" => &mut colors.heading,
+                "prompt" => &mut colors.prompt,
+                "selection_fg" => &mut colors.selection_fg,
+                "selection_bg" => &mut colors.selection_bg,
+                "match_bg" => &mut colors.match_bg,
+                "current_match_bg" => &mut colors.current_match_bg,
+                _ => {
+                    errors.push(format!("colors.{}: unknown color setting", name));
+                    continue;
+                }
+            };
+            match parse_color(value) {
+                Some(color) => *slot = color,
+                None => errors.push(format!("colors.{}: '{}' is not a color name or #rrggbb", name, value)),
+            }
+        }
+        colors
+    }
+}
+
+/// The shared config plus the editor's own settings from the same file
+#[derive(Debug, Clone)]
+pub struct EditorConfig {
+    pub default_mode: DisplayMode,
+    pub layout: LayoutParams,
+    pub colors: Colors,
+    pub keys: Keymap,
+}
+
+impl Default for EditorConfig {
+    fn default() -> Self {
+        let config = Config::default();
+        Self {
+            default_mode: config.default_mode,
+            layout: config.layout,
+            colors: Colors::default(),
+            keys: Keymap::default(),
+        }
+    }
+}
+
+impl EditorConfig {
+    /// Load the config file, or the defaults if there isn't one; problems in the shared settings
+    /// and in the editor's are listed together
+    pub fn load() -> Result<Self> {
+        let path = Config::path();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let (config, mut errors) = Config::read(&path)?;
+        let colors = Colors::build(&config.colors, &mut errors);
+        let keys = Keymap::build(&config.keys, &mut errors);
+        Config::check(&path, &errors)?;
+
+        Ok(Self {
+            default_mode: config.default_mode,
+            layout: config.layout,
+            colors,
+            keys,
+        })
+    }
+}
+
+// Named terminal colors (as crossterm spells them) or #rrggbb
+fn parse_color(value: &str) -> Option<Color> {
+    if let Some(hex) = value.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        return Some(Color::Rgb {
+            r: channel(0)?,
+            g: channel(2)?,
+            b: channel(4)?,
+        });
+    }
+    Color::try_from(value).ok()
+}
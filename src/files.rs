@@ -0,0 +1,42 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// Mac-specific file operations
+pub struct MacFileManager;
+
+impl MacFileManager {
+    /// Move files to Mac Trash instead of permanent deletion
+    pub fn move_to_trash(path: &Path) -> Result<()> {
+        trash::delete(path)?;
+        Ok(())
+    }
+
+    /// Get Mac-appropriate cache directory
+    pub fn get_cache_dir() -> PathBuf {
+        dirs::cache_dir()
+            .unwrap_or_else(|| PathBuf::from("/tmp"))
+            .join("chonker95")
+    }
+
+    /// Get Mac-appropriate documents directory
+    pub fn get_documents_dir() -> PathBuf {
+        dirs::document_dir()
+            .unwrap_or_else(|| dirs::home_dir().unwrap_or_else(|| PathBuf::from("/")))
+    }
+
+    /// Get Mac-appropriate config directory
+    pub fn get_config_dir() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| dirs::home_dir().unwrap_or_else(|| PathBuf::from("/")).join(".config"))
+            .join("chonker95")
+    }
+
+    /// Ensure directory exists with proper Mac permissions
+    pub fn ensure_dir_exists(path: &Path) -> Result<()> {
+        if !path.exists() {
+            std::fs::create_dir_all(path)?;
+        }
+        Ok(())
+    }
+}
+
@@ -0,0 +1,548 @@
+use unicode_width::UnicodeWidthChar;
+
+use crate::document::AltoElement;
+
+/// Where an element's text landed in the content grid
+#[derive(Debug, Clone)]
+pub struct GridPlacement {
+    pub id: String,
+    pub row: usize,
+    pub col: usize,
+    pub len: usize,
+    /// Whether the whole word is part of the current text buffer
+    pub in_buffer: bool,
+}
+
+/// Tunable constants for turning PDF points into terminal cells
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayoutParams {
+    /// Terminal cells per PDF point
+    pub scale: f32,
+    /// Smallest grid laid out, however little content the page has
+    pub min_cols: usize,
+    pub min_rows: usize,
+    /// PDF points per cell for `AltoElement` screen positions and text-only fallback pages
+    pub cell_width: f32,
+    pub cell_height: f32,
+}
+
+impl Default for LayoutParams {
+    fn default() -> Self {
+        Self {
+            scale: 0.15,
+            min_cols: 300,
+            min_rows: 100,
+            cell_width: 8.0,
+            cell_height: 12.0,
+        }
+    }
+}
+
+// Words whose baselines differ by less than this fraction of the taller one share a line
+const BASELINE_TOLERANCE: f32 = 0.5;
+// A horizontal gap wider than this many line heights splits a line (columns, table cells)
+const LINE_GAP: f32 = 1.5;
+// Lines further apart than this many line heights start a new block
+const BLOCK_GAP: f32 = 1.0;
+// Lines whose heights differ by more than this ratio (a heading over body text) start a new block
+const BLOCK_HEIGHT_RATIO: f32 = 1.3;
+// Colliding words move right by at most this many cells before their line wraps onto an extra row
+const MAX_SHIFT: usize = 3;
+
+/// Words sharing a baseline, left to right
+#[derive(Debug, Clone)]
+pub struct TextLine<'a> {
+    pub words: Vec<&'a AltoElement>,
+    /// Baseline of the line's tallest word, in PDF points from the top
+    pub baseline: f32,
+    pub hpos: f32,
+    pub vpos: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl<'a> TextLine<'a> {
+    fn new(words: Vec<&'a AltoElement>) -> Self {
+        let tallest = words.iter().copied().max_by(|a, b| a.height.total_cmp(&b.height));
+        let baseline = tallest.map_or(0.0, |word| word.baseline());
+        let (hpos, vpos, width, height) = bounds(words.iter().copied());
+        Self { words, baseline, hpos, vpos, width, height }
+    }
+}
+
+/// Lines close enough together to read as one paragraph, top to bottom
+#[derive(Debug, Clone)]
+pub struct TextBlock<'a> {
+    pub lines: Vec<TextLine<'a>>,
+    pub hpos: f32,
+    pub vpos: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl<'a> TextBlock<'a> {
+    fn new(line: TextLine<'a>) -> Self {
+        let mut block = Self { lines: Vec::new(), hpos: 0.0, vpos: 0.0, width: 0.0, height: 0.0 };
+        block.push(line);
+        block
+    }
+
+    fn push(&mut self, line: TextLine<'a>) {
+        self.lines.push(line);
+        (self.hpos, self.vpos, self.width, self.height) = bounds(self.words());
+    }
+
+    /// Every word of the block in reading order
+    pub fn words(&self) -> impl Iterator<Item = &'a AltoElement> + '_ {
+        self.lines.iter().flat_map(|line| line.words.iter().copied())
+    }
+
+    // Directly below the last line, overlapping horizontally and set in a similar size
+    fn continues_with(&self, line: &TextLine) -> bool {
+        let Some(last) = self.lines.last() else {
+            return true;
+        };
+        let gap = line.vpos - (last.vpos + last.height);
+        let overlaps = line.hpos < self.hpos + self.width && self.hpos < line.hpos + line.width;
+        let ratio = line.height.max(last.height) / line.height.min(last.height).max(0.1);
+        gap > -last.height / 2.0 && gap <= last.height * BLOCK_GAP && overlaps && ratio <= BLOCK_HEIGHT_RATIO
+    }
+}
+
+/// Cluster words into lines by baseline, splitting lines at wide horizontal gaps
+pub fn group_lines(elements: &[AltoElement]) -> Vec<TextLine<'_>> {
+    let mut sorted: Vec<&AltoElement> = elements.iter().collect();
+    sorted.sort_by(|a, b| a.baseline().total_cmp(&b.baseline()).then(a.hpos.total_cmp(&b.hpos)));
+
+    let mut rows: Vec<(f32, Vec<&AltoElement>)> = Vec::new();
+    for element in sorted {
+        let same_baseline = rows.last().is_some_and(|(baseline, words)| {
+            let height = words.iter().map(|w| w.height).fold(element.height, f32::max);
+            (element.baseline() - baseline).abs() <= height * BASELINE_TOLERANCE
+        });
+
+        if same_baseline {
+            rows.last_mut().unwrap().1.push(element);
+        } else {
+            rows.push((element.baseline(), vec![element]));
+        }
+    }
+
+    let mut lines = Vec::new();
+    for (_, mut words) in rows {
+        words.sort_by(|a, b| a.hpos.total_cmp(&b.hpos));
+
+        let mut start = 0;
+        for i in 1..=words.len() {
+            let split = i == words.len() || {
+                let (prev, next) = (words[i - 1], words[i]);
+                next.hpos - (prev.hpos + prev.width) > prev.height.max(next.height) * LINE_GAP
+            };
+            if split {
+                lines.push(TextLine::new(words[start..i].to_vec()));
+                start = i;
+            }
+        }
+    }
+    lines
+}
+
+/// Gather lines into blocks; each line joins the latest block it continues, else starts one
+pub fn group_blocks(mut lines: Vec<TextLine<'_>>) -> Vec<TextBlock<'_>> {
+    lines.sort_by(|a, b| a.vpos.total_cmp(&b.vpos).then(a.hpos.total_cmp(&b.hpos)));
+
+    let mut blocks: Vec<TextBlock> = Vec::new();
+    for line in lines {
+        match blocks.iter().rposition(|block| block.continues_with(&line)) {
+            Some(idx) => blocks[idx].push(line),
+            None => blocks.push(TextBlock::new(line)),
+        }
+    }
+    blocks
+}
+
+/// Order blocks for reading with a recursive XY-cut: split at the widest gap between block
+/// boxes, down the page between columns or across it between bands, and read each side in turn
+pub fn reading_order(blocks: Vec<TextBlock<'_>>) -> Vec<TextBlock<'_>> {
+    let boxes: Vec<(f32, f32, f32, f32)> = blocks.iter().map(|b| (b.hpos, b.vpos, b.width, b.height)).collect();
+    let mut order = Vec::with_capacity(blocks.len());
+    xy_cut(&boxes, (0..blocks.len()).collect(), &mut order);
+
+    let mut slots: Vec<Option<TextBlock>> = blocks.into_iter().map(Some).collect();
+    order.into_iter().filter_map(|idx| slots[idx].take()).collect()
+}
+
+fn xy_cut(boxes: &[(f32, f32, f32, f32)], mut region: Vec<usize>, order: &mut Vec<usize>) {
+    if region.len() <= 1 {
+        order.extend(region);
+        return;
+    }
+
+    let columns = widest_gap(region.iter().map(|&i| (boxes[i].0, boxes[i].0 + boxes[i].2)));
+    let bands = widest_gap(region.iter().map(|&i| (boxes[i].1, boxes[i].1 + boxes[i].3)));
+
+    // A gutter between columns wins a tie with a gap between bands
+    let (down, at) = match (columns, bands) {
+        (Some((x, gutter)), Some((_, gap))) if gutter >= gap => (true, x),
+        (Some((x, _)), None) => (true, x),
+        (_, Some((y, _))) => (false, y),
+        (None, None) => {
+            // Nothing separates these blocks cleanly; read them top to bottom
+            region.sort_by(|&a, &b| boxes[a].1.total_cmp(&boxes[b].1).then(boxes[a].0.total_cmp(&boxes[b].0)));
+            order.extend(region);
+            return;
+        }
+    };
+
+    let (first, second) = region
+        .into_iter()
+        .partition(|&i| if down { boxes[i].0 < at } else { boxes[i].1 < at });
+    xy_cut(boxes, first, order);
+    xy_cut(boxes, second, order);
+}
+
+// The middle and width of the widest empty stretch between intervals, if they don't all overlap
+fn widest_gap(intervals: impl Iterator<Item = (f32, f32)>) -> Option<(f32, f32)> {
+    let mut intervals: Vec<(f32, f32)> = intervals.collect();
+    intervals.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut widest: Option<(f32, f32)> = None;
+    let mut reach = f32::NEG_INFINITY;
+    for (start, end) in intervals {
+        if reach.is_finite() && start > reach && widest.is_none_or(|(_, gap)| start - reach > gap) {
+            widest = Some(((start + reach) / 2.0, start - reach));
+        }
+        reach = reach.max(end);
+    }
+    widest
+}
+
+/// Bounding box of elements as (hpos, vpos, width, height); all zero when there are none
+pub fn bounds<'a>(elements: impl Iterator<Item = &'a AltoElement>) -> (f32, f32, f32, f32) {
+    let (mut min_x, mut min_y) = (f32::INFINITY, f32::INFINITY);
+    let (mut max_x, mut max_y) = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+
+    for e in elements {
+        min_x = min_x.min(e.hpos);
+        min_y = min_y.min(e.vpos);
+        max_x = max_x.max(e.hpos + e.width);
+        max_y = max_y.max(e.vpos + e.height);
+    }
+
+    if min_x.is_infinite() {
+        return (0.0, 0.0, 0.0, 0.0);
+    }
+    (min_x, min_y, max_x - min_x, max_y - min_y)
+}
+
+// The first column at or after `start` where `len` cells are blank with a blank on either side
+fn first_fit(cells: Option<&Vec<char>>, start: usize, len: usize) -> usize {
+    let Some(cells) = cells else {
+        return start;
+    };
+    let blank = |x: usize| cells.get(x).is_none_or(|c| *c == ' ');
+
+    let mut col = start;
+    while !((col == 0 || blank(col - 1)) && (col..=col + len).all(blank)) {
+        col += 1;
+    }
+    col
+}
+
+/// Character grid laid out from element positions on the page
+#[derive(Debug, Clone, Default)]
+pub struct SpatialGrid {
+    pub cells: Vec<Vec<char>>,
+    pub width: usize,
+    pub height: usize,
+    /// Element spans in the grid and the PDF point at grid cell (0, 0)
+    pub placements: Vec<GridPlacement>,
+    pub origin: (f32, f32),
+    /// Cells per PDF point this grid was laid out with
+    pub scale: f32,
+    /// Words moved off their own spot because other text was already there
+    pub displaced: usize,
+}
+
+impl SpatialGrid {
+    /// Lay out elements into a grid; pure, so it works without a terminal
+    pub fn layout(elements: &[AltoElement], params: &LayoutParams) -> Self {
+        if elements.is_empty() {
+            return Self {
+                scale: params.scale,
+                ..Self::default()
+            };
+        }
+
+        // Find content bounds to determine required grid size
+        let min_x = elements.iter().map(|e| e.hpos).fold(f32::INFINITY, f32::min);
+        let max_x = elements.iter().map(|e| e.hpos + e.width).fold(f32::NEG_INFINITY, f32::max);
+        let min_y = elements.iter().map(|e| e.vpos).fold(f32::INFINITY, f32::min);
+        let max_y = elements.iter().map(|e| e.vpos + e.height).fold(f32::NEG_INFINITY, f32::max);
+
+        let content_width = (max_x - min_x).max(1.0);
+        let content_height = (max_y - min_y).max(1.0);
+
+        // Create unlimited grid based on actual content size
+        let scale_factor = params.scale; // Character scaling from PDF coordinates
+        let width = ((content_width * scale_factor) as usize + 50).max(params.min_cols);
+        let height = ((content_height * scale_factor) as usize + 20).max(params.min_rows);
+
+        let mut rows: Vec<Vec<char>> = Vec::new();
+        let mut placements = Vec::new();
+        let mut displaced = 0;
+        // Rows inserted for wrapped lines push every later line down
+        let mut extra_rows = 0;
+
+        // Place each line on its baseline's row, words left to right; nothing is dropped
+        for line in group_lines(elements) {
+            let mut row = ((line.baseline - min_y).max(0.0) * scale_factor) as usize + extra_rows;
+            // One cell past the line's previous word, so words stay separate tokens
+            let mut next_free = 0;
+
+            for element in &line.words {
+                let len: usize = element.content.chars().map(|ch| ch.width().unwrap_or(1)).sum();
+                let wanted = (((element.hpos - min_x) * scale_factor) as usize).max(next_free);
+
+                // Other lines' text in the way; too far to shift means continuing on a new row below
+                let mut col = first_fit(rows.get(row), wanted, len);
+                let wrapped = col - wanted > MAX_SHIFT;
+                if wrapped {
+                    row += 1;
+                    extra_rows += 1;
+                    col = first_fit(rows.get(row), wanted, len);
+                }
+                // Being pushed along by the previous word of the same line doesn't count
+                if wrapped || col != wanted {
+                    displaced += 1;
+                }
+
+                if rows.len() <= row {
+                    rows.resize(row + 1, Vec::new());
+                }
+                let cells = &mut rows[row];
+                if cells.len() < col + len {
+                    cells.resize(col + len, ' ');
+                }
+
+                let mut x = col;
+                for ch in element.content.chars() {
+                    match ch.width().unwrap_or(1) {
+                        0 => {}
+                        1 => cells[x] = ch,
+                        _ => {
+                            cells[x] = ch;
+                            cells[x + 1] = '\u{200B}'; // Zero-width space to mark continuation
+                        }
+                    }
+                    x += ch.width().unwrap_or(1);
+                }
+
+                placements.push(GridPlacement {
+                    id: element.id.clone(),
+                    row,
+                    col,
+                    len,
+                    in_buffer: false,
+                });
+                next_free = col + len + 1;
+            }
+        }
+
+        // Shifted words may run past the planned size; the grid grows instead of cutting them off
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0).max(width);
+        let height = rows.len().max(height + extra_rows);
+        rows.resize(height, Vec::new());
+        for cells in &mut rows {
+            cells.resize(width, ' ');
+        }
+
+        Self {
+            cells: rows,
+            width,
+            height,
+            placements,
+            origin: (min_x, min_y),
+            scale: scale_factor,
+            displaced,
+        }
+    }
+
+    /// Blocks in reading order as paragraphs wrapped to `width` columns, a blank row between blocks
+    pub fn reflow(elements: &[AltoElement], width: usize, params: &LayoutParams) -> Self {
+        let width = width.max(1);
+        let mut rows: Vec<Vec<char>> = Vec::new();
+        let mut placements = Vec::new();
+
+        for block in reading_order(group_blocks(group_lines(elements))) {
+            if !rows.is_empty() {
+                rows.push(Vec::new());
+            }
+
+            let mut row: Vec<char> = Vec::new();
+            for word in block.words() {
+                let len: usize = word.content.chars().map(|ch| ch.width().unwrap_or(1)).sum();
+                if !row.is_empty() && row.len() + 1 + len > width {
+                    rows.push(std::mem::take(&mut row));
+                }
+                if !row.is_empty() {
+                    row.push(' ');
+                }
+
+                let col = row.len();
+                for ch in word.content.chars() {
+                    match ch.width().unwrap_or(1) {
+                        0 => {}
+                        1 => row.push(ch),
+                        _ => row.extend([ch, '\u{200B}']), // Wide chars keep the continuation marker
+                    }
+                }
+                placements.push(GridPlacement {
+                    id: word.id.clone(),
+                    row: rows.len(),
+                    col,
+                    len: row.len() - col,
+                    in_buffer: false,
+                });
+            }
+            if !row.is_empty() {
+                rows.push(row);
+            }
+        }
+
+        // Words longer than the wrap width widen the grid rather than being cut
+        let grid_width = rows.iter().map(Vec::len).max().unwrap_or(0).max(width);
+        for row in &mut rows {
+            row.resize(grid_width, ' ');
+        }
+
+        Self {
+            width: grid_width,
+            height: rows.len(),
+            cells: rows,
+            placements,
+            origin: (0.0, 0.0),
+            scale: params.scale,
+            displaced: 0,
+        }
+    }
+
+    /// The whole grid as plain text, without continuation markers or trailing blanks
+    pub fn to_text(&self) -> String {
+        let mut result = String::new();
+        for row in &self.cells {
+            let line: String = row.iter().filter(|c| **c != '\u{200B}').collect();
+            result.push_str(line.trim_end());
+            result.push('\n');
+        }
+        result.trim_end().to_string()
+    }
+
+    /// A window of the grid as text, one line per row with trailing blanks trimmed
+    pub fn viewport_text(&self, offset_x: usize, offset_y: usize, width: usize, height: usize) -> String {
+        let mut result = String::new();
+
+        for screen_y in 0..height {
+            let grid_y = screen_y + offset_y;
+
+            if grid_y < self.height {
+                let mut line = String::new();
+
+                for screen_x in 0..width {
+                    let grid_x = screen_x + offset_x;
+
+                    if grid_x < self.width {
+                        line.push(self.cells[grid_y][grid_x]);
+                    } else {
+                        line.push(' ');
+                    }
+                }
+
+                result.push_str(line.trim_end());
+                result.push('\n');
+            } else {
+                result.push('\n');
+            }
+        }
+
+        result.trim_end().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(content: &str, hpos: f32, vpos: f32, baseline: f32) -> AltoElement {
+        let mut element = AltoElement::new(
+            content.to_string(),
+            content.to_string(),
+            hpos,
+            vpos,
+            content.len() as f32 * 5.0,
+            10.0,
+        );
+        element.baseline = Some(baseline);
+        element
+    }
+
+    #[test]
+    fn words_pushed_along_their_own_line_are_not_displaced() {
+        // 10pt text takes more cells than points at the default scale, so each word pushes the next
+        let mut hpos = 0.0;
+        let mut elements = Vec::new();
+        for text in "The quick brown fox jumps over the lazy dog".split(' ') {
+            elements.push(word(text, hpos, 0.0, 8.0));
+            hpos += text.len() as f32 * 5.0 + 2.5;
+        }
+
+        let grid = SpatialGrid::layout(&elements, &LayoutParams::default());
+        assert_eq!(grid.displaced, 0);
+        assert_eq!(grid.placements.len(), elements.len());
+        assert!(grid.to_text().contains("The quick brown fox jumps over the lazy dog"));
+    }
+
+    #[test]
+    fn words_forced_off_their_spot_by_another_line_are_displaced() {
+        // Baselines too far apart to share a line but close enough to share a grid row
+        let elements = vec![
+            word("upper", 0.0, 0.0, 7.0),
+            word("line", 40.0, 0.0, 7.0),
+            word("lower", 1.0, 5.0, 12.5),
+            word("text", 41.0, 5.0, 12.5),
+        ];
+
+        let grid = SpatialGrid::layout(&elements, &LayoutParams::default());
+        let row_of = |id: &str| grid.placements.iter().find(|p| p.id == id).map(|p| p.row);
+        assert_eq!(row_of("upper"), row_of("line"));
+        assert_eq!(row_of("lower"), row_of("upper").map(|row| row + 1));
+        assert_eq!(row_of("text"), row_of("lower"));
+        assert_eq!(grid.displaced, 1);
+        assert_eq!(grid.placements.len(), elements.len());
+    }
+
+    #[test]
+    fn full_width_title_is_read_before_both_columns() {
+        let mut elements = Vec::new();
+        // Title running across the gutter, then two columns; the right one starts a little higher
+        for i in 0..6 {
+            elements.push(word(&format!("title{:03}", i), i as f32 * 45.0, 0.0, 8.0));
+        }
+        for i in 0..3 {
+            let vpos = 30.0 + i as f32 * 12.0;
+            elements.push(word(&format!("left{:04}", i), 0.0, vpos, vpos + 8.0));
+            elements.push(word(&format!("rght{:04}", i), 180.0, vpos - 2.0, vpos + 6.0));
+        }
+
+        let blocks = reading_order(group_blocks(group_lines(&elements)));
+        let text: Vec<Vec<&str>> = blocks.iter().map(|b| b.words().map(|w| w.content.as_str()).collect()).collect();
+        assert_eq!(
+            text,
+            vec![
+                vec!["title000", "title001", "title002", "title003", "title004", "title005"],
+                vec!["left0000", "left0001", "left0002"],
+                vec!["rght0000", "rght0001", "rght0002"],
+            ]
+        );
+    }
+}
@@ -0,0 +1,19 @@
+//! Extraction, layout and export logic shared by the chonker95 front-ends
+
+pub mod alto;
+pub mod config;
+pub mod corrections;
+pub mod document;
+pub mod files;
+pub mod layout;
+pub mod logging;
+pub mod search;
+
+pub use alto::AltoWriter;
+pub use config::{Config, DisplayMode};
+pub use corrections::{Correction, CorrectionStore, EditSnapshot, PageCorrections, PageHistory};
+pub use document::{body_font_size, parse_page_ranges, AltoElement, AltoPage, Document, FontStyle, TextHit};
+pub use files::MacFileManager;
+pub use layout::{group_blocks, group_lines, reading_order, GridPlacement, LayoutParams, SpatialGrid, TextBlock, TextLine};
+pub use search::{find_in_grid, GridMatch, SearchQuery};
+pub use pdfium_render::prelude::Pdfium;
@@ -0,0 +1,110 @@
+use anyhow::Result;
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::files::MacFileManager;
+
+/// Environment variable read when no level is given on the command line
+pub const LOG_ENV: &str = "CHONKER95_LOG";
+
+/// Logs larger than this are moved aside to `.log.1` at startup
+const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Appends one logfmt line per record, so logs can be grepped and attached to bug reports
+struct FileLogger {
+    level: LevelFilter,
+    file: Mutex<File>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or_default();
+        // Quotes and line breaks are escaped so every record stays on one line
+        let message = record
+            .args()
+            .to_string()
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r");
+        let line = format!(
+            "ts={:.3} level={} target={} msg=\"{}\"\n",
+            timestamp,
+            record.level(),
+            record.target(),
+            message
+        );
+
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.write_all(line.as_bytes());
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+/// Where the log file lives
+pub fn log_path() -> PathBuf {
+    MacFileManager::get_cache_dir().join("chonker95.log")
+}
+
+/// Parse a verbosity name: off, error, warn, info, debug or trace
+pub fn parse_level(name: &str) -> Result<LevelFilter> {
+    name.trim()
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Unknown log level '{}' (expected off, error, warn, info, debug or trace)", name))
+}
+
+/// Start logging to the cache directory; the CLI level wins over the environment, then `warn`
+pub fn init(cli_level: Option<&str>) -> Result<LevelFilter> {
+    let level = match cli_level {
+        Some(name) => parse_level(name)?,
+        None => match std::env::var(LOG_ENV) {
+            Ok(name) => parse_level(&name)?,
+            Err(_) => LevelFilter::Warn,
+        },
+    };
+    if level == LevelFilter::Off {
+        return Ok(level);
+    }
+
+    let path = log_path();
+    if let Some(dir) = path.parent() {
+        MacFileManager::ensure_dir_exists(dir)?;
+    }
+    rotate(&path)?;
+
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+    log::set_boxed_logger(Box::new(FileLogger {
+        level,
+        file: Mutex::new(file),
+    }))?;
+    log::set_max_level(level);
+    Ok(level)
+}
+
+fn rotate(path: &Path) -> Result<()> {
+    if std::fs::metadata(path).is_ok_and(|meta| meta.len() > MAX_LOG_BYTES) {
+        std::fs::rename(path, path.with_extension("log.1"))?;
+    }
+    Ok(())
+}
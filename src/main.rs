@@ -1,17 +1,27 @@
 use anyhow::Result;
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
 use crossterm::{
     cursor,
     event::{self, Event, KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
-    execute,
-    style::{Color, Print, ResetColor, SetForegroundColor, SetBackgroundColor},
+    execute, queue,
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor, SetBackgroundColor},
     terminal::{self, Clear, ClearType},
 };
-use pdfium_render::prelude::*;
+use chonker95::{
+    body_font_size, parse_page_ranges, AltoElement, AltoPage, AltoWriter, CorrectionStore, Document, GridPlacement,
+    find_in_grid, EditSnapshot, GridMatch, MacFileManager, PageCorrections, Pdfium, SearchQuery, SpatialGrid,
+    TextHit, logging, Config, DisplayMode,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::io::{self, Write};
-use std::path::PathBuf;
-use unicode_width::UnicodeWidthChar;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+mod editor_config;
+
+use editor_config::{Action, EditorConfig};
 
 #[derive(Serialize, Deserialize, Debug)]
 enum SyncMessage {
@@ -39,7 +49,11 @@ impl SyncManager {
             let json = serde_json::to_string(&message)?;
             use std::io::Write;
             let mut stream = stream;
-            let _ = writeln!(stream, "{}", json);
+            // No listener just means there's no other pane; a failed write is a real error
+            writeln!(stream, "{}", json)?;
+            log::debug!("sync -> {}: {}", self.socket_path.display(), json);
+        } else {
+            log::trace!("sync: no listener at {}", self.socket_path.display());
         }
         Ok(())
     }
@@ -52,6 +66,291 @@ impl SyncManager {
     }
 }
 
+// How serious a status message is, which sets its color and how long it stays up
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MessageLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+impl MessageLevel {
+    fn color(self) -> Color {
+        match self {
+            MessageLevel::Info => Color::Green,
+            MessageLevel::Warning => Color::Yellow,
+            MessageLevel::Error => Color::Red,
+        }
+    }
+
+    fn duration(self) -> Duration {
+        match self {
+            MessageLevel::Info => Duration::from_secs(4),
+            MessageLevel::Warning => Duration::from_secs(8),
+            MessageLevel::Error => Duration::from_secs(12),
+        }
+    }
+
+    fn tag(self) -> &'static str {
+        match self {
+            MessageLevel::Info => "INFO",
+            MessageLevel::Warning => "WARN",
+            MessageLevel::Error => "ERROR",
+        }
+    }
+}
+
+struct StatusMessage {
+    level: MessageLevel,
+    text: String,
+    at: Instant,
+}
+
+// Messages shown in the status line until they expire, kept for the history view
+struct MessageLog {
+    history: VecDeque<StatusMessage>,
+}
+
+impl MessageLog {
+    const MAX_HISTORY: usize = 200;
+
+    fn new() -> Self {
+        Self { history: VecDeque::new() }
+    }
+
+    fn push(&mut self, level: MessageLevel, text: String) {
+        self.history.push_back(StatusMessage {
+            level,
+            text,
+            at: Instant::now(),
+        });
+        if self.history.len() > Self::MAX_HISTORY {
+            self.history.pop_front();
+        }
+    }
+
+    // The newest message, if it hasn't expired yet
+    fn current(&self) -> Option<&StatusMessage> {
+        self.history
+            .back()
+            .filter(|message| message.at.elapsed() < message.level.duration())
+    }
+}
+
+// Pages queued for extraction ahead of a visit; PDFium isn't thread-safe, so they are
+// extracted on the UI thread, one whenever it is idle waiting for input
+struct PagePrefetcher {
+    queue: VecDeque<u32>,
+}
+
+impl PagePrefetcher {
+    fn new() -> Self {
+        Self { queue: VecDeque::new() }
+    }
+
+    fn request(&mut self, number: u32) {
+        if !self.queue.contains(&number) {
+            self.queue.push_back(number);
+        }
+    }
+
+    // Forget queued pages, e.g. after jumping away from them
+    fn clear(&mut self) {
+        self.queue.clear();
+    }
+
+    fn next(&mut self) -> Option<u32> {
+        self.queue.pop_front()
+    }
+
+    fn is_idle(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+// How a word stands out from the page's body text
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Emphasis {
+    Heading,
+    Bold,
+}
+
+// One terminal cell of a rendered frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Cell {
+    ch: char,
+    fg: Color,
+    bg: Color,
+    bold: bool,
+}
+
+impl Cell {
+    // Second half of a double-width character, drawn by the cell before it
+    const CONTINUATION: char = '\0';
+    // Never produced by rendering, so an invalidated cell always differs
+    const INVALID: char = '\u{FFFF}';
+
+    fn blank() -> Self {
+        Self { ch: ' ', fg: Color::Reset, bg: Color::Reset, bold: false }
+    }
+}
+
+// A full screen of cells, built fresh for every render
+struct Frame {
+    width: u16,
+    height: u16,
+    cells: Vec<Cell>,
+}
+
+impl Frame {
+    fn new(width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![Cell::blank(); width as usize * height as usize],
+        }
+    }
+
+    fn cell(&self, x: u16, y: u16) -> Cell {
+        self.cells[y as usize * self.width as usize + x as usize]
+    }
+
+    fn set(&mut self, x: u16, y: u16, cell: Cell) {
+        self.cells[y as usize * self.width as usize + x as usize] = cell;
+    }
+
+    // Write text at (x, y), clipped to the frame width
+    fn put_str(&mut self, x: u16, y: u16, text: &str, fg: Color, bg: Color) {
+        if y >= self.height {
+            return;
+        }
+
+        let mut col = x as usize;
+        for ch in text.chars() {
+            // Zero-width chars (including the grid's wide-char marker) take no cell
+            let char_width = ch.width().unwrap_or(0);
+            if char_width == 0 {
+                continue;
+            }
+            if col + char_width > self.width as usize {
+                break;
+            }
+
+            self.set(col as u16, y, Cell { ch, fg, bg, bold: false });
+            if char_width == 2 {
+                self.set(col as u16 + 1, y, Cell { ch: Cell::CONTINUATION, fg, bg, bold: false });
+            }
+            col += char_width;
+        }
+    }
+
+    // Restyle cells already written, keeping their text; clipped to the frame
+    fn emphasize(&mut self, x: u16, y: u16, len: usize, fg: Option<Color>) {
+        if y >= self.height {
+            return;
+        }
+
+        let end = (x as usize + len).min(self.width as usize);
+        for col in x as usize..end {
+            let mut cell = self.cell(col as u16, y);
+            cell.bold = true;
+            if let Some(fg) = fg {
+                cell.fg = fg;
+            }
+            self.set(col as u16, y, cell);
+        }
+    }
+}
+
+// Double-buffered terminal output that only writes cells changed since the last frame
+struct Screen {
+    previous: Option<Frame>,
+}
+
+impl Screen {
+    fn new() -> Self {
+        Self { previous: None }
+    }
+
+    // Forget the last frame so the next draw repaints everything
+    fn invalidate(&mut self) {
+        self.previous = None;
+    }
+
+    // Mark a row as overwritten by something drawn outside the frame model
+    fn invalidate_row(&mut self, y: u16) {
+        if let Some(previous) = self.previous.as_mut().filter(|p| y < p.height) {
+            for x in 0..previous.width {
+                previous.set(x, y, Cell { ch: Cell::INVALID, ..Cell::blank() });
+            }
+        }
+    }
+
+    fn draw(&mut self, frame: Frame, out: &mut impl Write) -> Result<()> {
+        let previous = self.previous.take()
+            .filter(|p| p.width == frame.width && p.height == frame.height);
+        let changed = |x: u16, y: u16| match &previous {
+            Some(p) => p.cell(x, y) != frame.cell(x, y),
+            None => true,
+        };
+
+        if previous.is_none() {
+            queue!(out, ResetColor, Clear(ClearType::All))?;
+        }
+
+        let (mut fg, mut bg, mut bold) = (Color::Reset, Color::Reset, false);
+        queue!(out, ResetColor, SetAttribute(Attribute::Reset))?;
+
+        for y in 0..frame.height {
+            let mut x = 0;
+            while x < frame.width {
+                if !changed(x, y) {
+                    x += 1;
+                    continue;
+                }
+
+                // A changed run starting on a continuation cell redraws its wide char
+                let mut start = x;
+                if frame.cell(start, y).ch == Cell::CONTINUATION && start > 0 {
+                    start -= 1;
+                }
+                let mut end = x;
+                while end < frame.width && changed(end, y) {
+                    end += 1;
+                }
+
+                queue!(out, cursor::MoveTo(start, y))?;
+                for cx in start..end {
+                    let cell = frame.cell(cx, y);
+                    if cell.ch == Cell::CONTINUATION {
+                        continue;
+                    }
+                    if cell.fg != fg {
+                        queue!(out, SetForegroundColor(cell.fg))?;
+                        fg = cell.fg;
+                    }
+                    if cell.bg != bg {
+                        queue!(out, SetBackgroundColor(cell.bg))?;
+                        bg = cell.bg;
+                    }
+                    if cell.bold != bold {
+                        let attribute = if cell.bold { Attribute::Bold } else { Attribute::NormalIntensity };
+                        queue!(out, SetAttribute(attribute))?;
+                        bold = cell.bold;
+                    }
+                    queue!(out, Print(cell.ch))?;
+                }
+
+                x = end;
+            }
+        }
+
+        queue!(out, ResetColor, SetAttribute(Attribute::Reset))?;
+        self.previous = Some(frame);
+        Ok(())
+    }
+}
+
 // Kitty terminal detection and quirk handling
 struct TerminalInfo {
     is_kitty: bool,
@@ -86,95 +385,186 @@ impl TerminalInfo {
 }
 
 #[derive(Parser)]
-#[command(author, version, about)]
+#[command(author, version, about, args_conflicts_with_subcommands = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// PDF file to process (default: the last file opened)
+    file: Option<PathBuf>,
+
+    /// Page number to extract (default: 1, or the saved page when resuming)
+    #[arg(short, long)]
+    page: Option<u32>,
+
+    /// Restore the saved page, cursor and viewport for the file
+    #[arg(long)]
+    resume: bool,
+
+    /// Maximum viewport width in columns (default: full terminal width)
+    #[arg(long)]
+    max_cols: Option<usize>,
+
+    /// Maximum viewport height in rows (default: full terminal height)
+    #[arg(long)]
+    max_rows: Option<usize>,
+
+    /// Log verbosity: off, error, warn, info, debug or trace (default: $CHONKER95_LOG, else warn)
+    #[arg(long, global = true, value_name = "LEVEL")]
+    log_level: Option<String>,
+}
+
+// Optional user caps on the viewport size
+#[derive(Debug, Clone, Copy, Default)]
+struct ViewportLimits {
+    max_cols: Option<usize>,
+    max_rows: Option<usize>,
+}
+
+// Headless subcommands that never touch the terminal
+#[derive(Subcommand)]
+enum Command {
+    /// Extract word elements with their PDF coordinates as JSON
+    Extract(BatchArgs),
+    /// Render pages as spatially laid out plain text
+    Render(BatchArgs),
+    /// Export pages as ALTO v4 XML
+    Export(BatchArgs),
+}
+
+#[derive(Args)]
+struct BatchArgs {
     /// PDF file to process
     file: PathBuf,
-    
-    /// Page number to extract (default: 1)
-    #[arg(short, long, default_value_t = 1)]
+
+    /// Pages to process, e.g. "1-3,7,10-" (default: all pages)
+    #[arg(short, long)]
+    pages: Option<String>,
+
+    /// Write to this file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Ignore saved corrections and use the raw extraction
+    #[arg(long)]
+    raw: bool,
+}
+
+// Where the user left off in one file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SessionEntry {
+    file: PathBuf,
     page: u32,
+    display_mode: DisplayMode,
+    cursor_x: u16,
+    cursor_y: u16,
+    viewport_offset_x: usize,
+    viewport_offset_y: usize,
 }
 
-#[derive(Debug, Clone)]
-struct AltoElement {
-    id: String,
-    content: String,
-    hpos: f32,
-    vpos: f32,
-    width: f32,
-    height: f32,
-    // Screen position (calculated from PDF coordinates)
-    screen_x: u16,
-    screen_y: u16,
+// Most-recent-first list of sessions, stored as last_session.json
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SessionState {
+    recent: Vec<SessionEntry>,
 }
 
-impl AltoElement {
-    fn new(id: String, content: String, hpos: f32, vpos: f32, width: f32, height: f32) -> Self {
-        let screen_x = (hpos / 8.0) as u16; // Convert PDF coords to terminal coords
-        let screen_y = (vpos / 12.0) as u16;
-        
-        Self {
-            id,
-            content,
-            hpos,
-            vpos,
-            width,
-            height,
-            screen_x,
-            screen_y,
-        }
+impl SessionState {
+    const MAX_RECENT: usize = 10;
+
+    fn path() -> PathBuf {
+        MacFileManager::get_config_dir().join("last_session.json")
     }
-}
 
-// Display modes for the editor
-#[derive(Debug, Clone, PartialEq)]
-enum DisplayMode {
-    TextOnly,
-    SplitScreen,
-}
+    // Missing or unreadable session files just mean there's nothing to resume
+    fn load() -> Self {
+        let Ok(data) = std::fs::read_to_string(Self::path()) else {
+            return Self::default();
+        };
 
-// Mac-specific file operations
-struct MacFileManager;
+        if let Ok(state) = serde_json::from_str::<Self>(&data) {
+            return state;
+        }
 
-impl MacFileManager {
-    // Move files to Mac Trash instead of permanent deletion
-    fn move_to_trash(path: &PathBuf) -> Result<()> {
-        trash::delete(path)?;
-        Ok(())
+        // Older versions stored a single entry with only file, page and mode
+        let Ok(legacy) = serde_json::from_str::<serde_json::Value>(&data) else {
+            return Self::default();
+        };
+        let Some(file) = legacy["last_file"].as_str() else {
+            return Self::default();
+        };
+
+        Self {
+            recent: vec![SessionEntry {
+                file: PathBuf::from(file),
+                page: legacy["current_page"].as_u64().unwrap_or(1) as u32,
+                display_mode: serde_json::from_value(legacy["display_mode"].clone())
+                    .unwrap_or(DisplayMode::TextOnly),
+                cursor_x: 0,
+                cursor_y: 0,
+                viewport_offset_x: 0,
+                viewport_offset_y: 0,
+            }],
+        }
     }
 
-    // Get Mac-appropriate cache directory
-    fn get_cache_dir() -> PathBuf {
-        dirs::cache_dir()
-            .unwrap_or_else(|| PathBuf::from("/tmp"))
-            .join("chonker95")
+    fn save(&self) -> Result<()> {
+        std::fs::write(Self::path(), serde_json::to_string_pretty(self)?)?;
+        Ok(())
     }
 
-    // Get Mac-appropriate documents directory
-    fn get_documents_dir() -> PathBuf {
-        dirs::document_dir()
-            .unwrap_or_else(|| dirs::home_dir().unwrap_or_else(|| PathBuf::from("/")))
+    fn last(&self) -> Option<&SessionEntry> {
+        self.recent.first()
     }
 
-    // Get Mac-appropriate config directory
-    fn get_config_dir() -> PathBuf {
-        dirs::config_dir()
-            .unwrap_or_else(|| dirs::home_dir().unwrap_or_else(|| PathBuf::from("/")).join(".config"))
-            .join("chonker95")
+    fn find(&self, file: &Path) -> Option<&SessionEntry> {
+        let file = file.canonicalize().unwrap_or_else(|_| file.to_path_buf());
+        self.recent.iter().find(|entry| entry.file == file)
     }
 
-    // Ensure directory exists with proper Mac permissions
-    fn ensure_dir_exists(path: &PathBuf) -> Result<()> {
-        if !path.exists() {
-            std::fs::create_dir_all(path)?;
-        }
-        Ok(())
+    // Move the entry's file to the front of the list
+    fn record(&mut self, entry: SessionEntry) {
+        self.recent.retain(|existing| existing.file != entry.file);
+        self.recent.insert(0, entry);
+        self.recent.truncate(Self::MAX_RECENT);
     }
 }
 
-struct WysiwygEditor {
+// Find/replace prompt shown in place of the status line
+#[derive(Default)]
+struct SearchPrompt {
+    query: SearchQuery,
+    replacement: String,
+    // Replace field is shown, and whether typing goes to it
+    replacing: bool,
+    editing_replacement: bool,
+    // Matches on the current page's grid and the one the cursor is on
+    matches: Vec<GridMatch>,
+    current: Option<usize>,
+    message: Option<String>,
+}
+
+// Document-wide hits from PDFium's text search, listed over the text area
+struct SearchResults {
+    phrase: String,
+    hits: Vec<TextHit>,
+    selected: usize,
+    // First hit shown, so the selection stays on screen
+    scroll: usize,
+}
+
+struct WysiwygEditor<'a> {
+    // The PDF stays loaded for the editor's lifetime
+    document: Document<'a>,
+    // Extraction results of the pages visited or prefetched around the current one
+    page_cache: HashMap<u32, AltoPage>,
+    // Uncorrected layouts of prefetched pages, and the queue of pages to prefetch
+    grid_cache: HashMap<u32, SpatialGrid>,
+    prefetcher: PagePrefetcher,
+    // Elements as extracted from the PDF, and with user corrections applied
+    original_elements: Vec<AltoElement>,
     elements: Vec<AltoElement>,
+    // Corrections for every page of this document
+    corrections: CorrectionStore,
     pdf_path: PathBuf,
     current_page: u32,
     terminal_width: u16,
@@ -183,20 +573,42 @@ struct WysiwygEditor {
     text_buffer: String,
     cursor_x: u16,
     cursor_y: u16,
-    // Selection state
-    is_all_selected: bool,
+    // Selection runs from the anchor to the cursor
+    selection_anchor: Option<(u16, u16)>,
+    // Treat the anchor and cursor as opposite corners of a rectangle
+    block_selection: bool,
+    // Cursor position after the last typed character, while a run of typing can still be undone as one step
+    typing_run_end: Option<(u16, u16)>,
+    // Active find/replace prompt, if any
+    search: Option<SearchPrompt>,
+    // Open document search results list, if any
+    search_results: Option<SearchResults>,
+    // Text typed into the go-to-page prompt while it's open
+    page_prompt: Option<String>,
+    // Label of the current page, when the PDF defines one
+    page_label: Option<String>,
+    // Status messages, and the scroll position of the history view while it's open
+    messages: MessageLog,
+    message_history: Option<usize>,
+    // Keymap, colors and layout constants from the user's config file
+    config: EditorConfig,
+    // Time, position and count of recent clicks for double/triple-click
+    last_click: Option<(Instant, u16, u16, u8)>,
     clipboard: String,
     // Viewport scrolling for large documents
     viewport_offset_x: usize,
     viewport_offset_y: usize,
+    viewport_limits: ViewportLimits,
     // Full content grid (unlimited size)
-    content_grid: Vec<Vec<char>>,
-    grid_width: usize,
-    grid_height: usize,
+    grid: SpatialGrid,
+    // Words drawn bold or as headings, by element id
+    emphasis: HashMap<String, Emphasis>,
     // Terminal info for quirk handling
     terminal_info: TerminalInfo,
     // State to prevent ANSI hell
     terminal_state_clean: bool,
+    // Last drawn frame for differential rendering
+    screen: Screen,
     // Display mode
     display_mode: DisplayMode,
     // Sync manager for pane communication
@@ -205,15 +617,36 @@ struct WysiwygEditor {
     file_manager: MacFileManager,
 }
 
-impl WysiwygEditor {
-    fn new(pdf_path: PathBuf, page: u32) -> Result<Self> {
+impl<'a> WysiwygEditor<'a> {
+    // Pages kept extracted at once; a long document isn't held in memory whole
+    const PAGE_CACHE_SIZE: usize = 16;
+
+    fn new(
+        pdfium: &'a Pdfium,
+        pdf_path: PathBuf,
+        page: u32,
+        viewport_limits: ViewportLimits,
+        config: EditorConfig,
+    ) -> Result<Self> {
         let (width, height) = terminal::size()?;
         let terminal_info = TerminalInfo::detect();
 
+        let document = Document::open(pdfium, &pdf_path)?.with_layout(config.layout);
+        // A stale session or a typo'd --page shouldn't open an empty page
+        let page = page.clamp(1, document.page_count().max(1));
+        let prefetcher = PagePrefetcher::new();
+
         let sync_manager = SyncManager::new(&pdf_path);
+        let corrections = CorrectionStore::open(&pdf_path)?;
 
         let mut editor = Self {
+            document,
+            page_cache: HashMap::new(),
+            grid_cache: HashMap::new(),
+            prefetcher,
+            original_elements: Vec::new(),
             elements: Vec::new(),
+            corrections,
             pdf_path,
             current_page: page,
             terminal_width: width,
@@ -221,34 +654,78 @@ impl WysiwygEditor {
             text_buffer: String::new(),
             cursor_x: 0,
             cursor_y: 0,
-            is_all_selected: false,
+            selection_anchor: None,
+            block_selection: false,
+            typing_run_end: None,
+            search: None,
+            search_results: None,
+            page_prompt: None,
+            page_label: None,
+            messages: MessageLog::new(),
+            message_history: None,
+            last_click: None,
             clipboard: String::new(),
             viewport_offset_x: 0,
             viewport_offset_y: 0,
-            content_grid: Vec::new(),
-            grid_width: 0,
-            grid_height: 0,
+            viewport_limits,
+            grid: SpatialGrid::default(),
+            emphasis: HashMap::new(),
             terminal_info,
             terminal_state_clean: true,
+            screen: Screen::new(),
             display_mode: DisplayMode::TextOnly,
             sync_manager,
             file_manager: MacFileManager,
+            config,
         };
 
         // Initialize Mac-specific directories
         editor.init_mac_directories()?;
 
         editor.load_page()?;
+        if let Some(aside) = editor.corrections.quarantined() {
+            let text = format!("Saved corrections couldn't be used and were moved to {}", aside.display());
+            editor.notify(MessageLevel::Warning, text);
+        }
         Ok(editor)
     }
     
     fn load_page(&mut self) -> Result<()> {
-        self.elements = self.extract_alto_elements()?;
+        log::debug!("loading page {}", self.current_page);
+
+        // Persist corrections made on the page we're leaving
+        self.corrections.save(&self.pdf_path)?;
+
+        self.typing_run_end = None;
+        // A selection on the previous page would otherwise cover the same cells of this one
+        self.clear_selection();
+        self.page_label = self.document.page_label(self.current_page);
+        self.original_elements = self.extract_alto_elements()?;
+        self.elements = match self.corrections.get(self.current_page) {
+            Some(corrections) => corrections.apply(&self.original_elements),
+            None => self.original_elements.clone(),
+        };
+        let unmatched = self.corrections.get(self.current_page).map_or(0, |c| c.unmatched(&self.original_elements));
+        if unmatched > 0 {
+            self.notify(
+                MessageLevel::Warning,
+                format!("{} saved corrections no longer match the extracted words and were skipped", unmatched),
+            );
+        }
         self.rebuild_text_buffer();
+        self.prefetch_adjacent_pages();
+
+        // Overlapping text is moved rather than hidden; say so, since it no longer matches the PDF exactly
+        if self.grid.displaced > 0 {
+            let displaced = self.grid.displaced;
+            self.notify(MessageLevel::Info, format!("{} words moved to avoid overlapping text", displaced));
+        }
 
         // Sync page with external viewer if in split-screen mode
-        if self.display_mode == DisplayMode::SplitScreen {
-            let _ = self.sync_external_viewer_page();
+        if self.display_mode == DisplayMode::SplitScreen
+            && let Err(e) = self.sync_external_viewer_page()
+        {
+            self.notify(MessageLevel::Warning, format!("Couldn't sync the PDF viewer: {}", e));
         }
 
         Ok(())
@@ -302,28 +779,46 @@ impl WysiwygEditor {
 
     // Save current session state to Mac config directory
     fn save_session_state(&self) -> Result<()> {
-        let config_dir = MacFileManager::get_config_dir();
-        let session_file = config_dir.join("last_session.json");
-
-        let session_data = serde_json::json!({
-            "last_file": self.pdf_path.to_string_lossy(),
-            "current_page": self.current_page,
-            "display_mode": match self.display_mode {
-                DisplayMode::TextOnly => "text_only",
-                DisplayMode::SplitScreen => "split_screen",
-            },
-            "terminal_size": {
-                "width": self.terminal_width,
-                "height": self.terminal_height
-            }
+        let file = self.pdf_path.canonicalize().unwrap_or_else(|_| self.pdf_path.clone());
+
+        let mut state = SessionState::load();
+        state.record(SessionEntry {
+            file,
+            page: self.current_page,
+            display_mode: self.display_mode,
+            cursor_x: self.cursor_x,
+            cursor_y: self.cursor_y,
+            viewport_offset_x: self.viewport_offset_x,
+            viewport_offset_y: self.viewport_offset_y,
         });
+        state.save()
+    }
+
+    // Put the editor back where a previous session left off
+    fn restore_session(&mut self, entry: &SessionEntry) -> Result<()> {
+        // Lay out in the saved mode first, since a reflowed grid has a different size
+        if entry.display_mode == DisplayMode::Reflow {
+            self.display_mode = DisplayMode::Reflow;
+            self.rebuild_text_buffer();
+        }
+
+        // The terminal may be smaller than last time; keep the viewport inside the grid and the cursor inside the viewport
+        let (viewport_width, viewport_height) = self.viewport_size();
+        self.viewport_offset_x = entry.viewport_offset_x.min(self.grid.width.saturating_sub(viewport_width));
+        self.viewport_offset_y = entry.viewport_offset_y.min(self.grid.height.saturating_sub(viewport_height));
+        self.cursor_x = entry.cursor_x.min(viewport_width.saturating_sub(1) as u16);
+        self.cursor_y = entry.cursor_y.min(viewport_height.saturating_sub(1) as u16);
+        self.rebuild_text_buffer();
+
+        if entry.display_mode == DisplayMode::SplitScreen && Self::is_in_zellij() {
+            self.open_viuer_pane()?;
+        }
 
-        std::fs::write(session_file, session_data.to_string())?;
         Ok(())
     }
 
     // Save extracted text to Mac Documents directory
-    fn save_extracted_text(&self) -> Result<()> {
+    fn save_extracted_text(&mut self) -> Result<()> {
         let docs_dir = MacFileManager::get_documents_dir();
         MacFileManager::ensure_dir_exists(&docs_dir)?;
 
@@ -335,9 +830,56 @@ impl WysiwygEditor {
         let output_file = docs_dir.join(format!("{}_page_{}_extracted.txt", filename, self.current_page));
 
         std::fs::write(&output_file, &self.text_buffer)?;
+        self.notify(MessageLevel::Info, format!("Saved extracted text to {}", output_file.display()));
+
+        Ok(())
+    }
+
+    // Export the current page as ALTO XML to Mac Documents directory
+    fn export_alto_page(&mut self) -> Result<()> {
+        let docs_dir = MacFileManager::get_documents_dir();
+        MacFileManager::ensure_dir_exists(&docs_dir)?;
+
+        let (width, height) = self.current_page_size()?;
+        let page = AltoPage {
+            number: self.current_page,
+            width,
+            height,
+            elements: self.elements.clone(),
+        };
+
+        let filename = self.pdf_path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy();
+
+        let output_file = docs_dir.join(format!("{}_page_{}.alto.xml", filename, self.current_page));
+        std::fs::write(&output_file, AltoWriter::write_document(&self.pdf_path, &[page]))?;
+        self.notify(MessageLevel::Info, format!("Exported page {} as ALTO XML to {}", self.current_page, output_file.display()));
+
+        Ok(())
+    }
+
+    // Export every page of the document as a single ALTO XML file
+    fn export_alto_document(&mut self) -> Result<()> {
+        let docs_dir = MacFileManager::get_documents_dir();
+        MacFileManager::ensure_dir_exists(&docs_dir)?;
+
+        let mut pages = self.extract_all_pages()?;
+        for page in &mut pages {
+            if let Some(corrections) = self.corrections.get(page.number) {
+                page.elements = corrections.apply(&page.elements);
+            }
+        }
+
+        let filename = self.pdf_path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy();
 
-        // Show brief confirmation in status (would need status message system)
-        eprintln!("Saved extracted text to: {}", output_file.display());
+        let output_file = docs_dir.join(format!("{}.alto.xml", filename));
+        std::fs::write(&output_file, AltoWriter::write_document(&self.pdf_path, &pages))?;
+        self.notify(MessageLevel::Info, format!("Exported {} pages as ALTO XML to {}", pages.len(), output_file.display()));
 
         Ok(())
     }
@@ -361,18 +903,80 @@ impl WysiwygEditor {
                 self.display_mode = DisplayMode::TextOnly;
             } else {
                 // Only one pane - create new viuer pane
-                std::process::Command::new("zellij")
-                    .arg("action")
-                    .arg("new-pane")
-                    .arg("--direction")
-                    .arg("left")
-                    .arg("--")
-                    .arg("/Users/jack/chonker95/viuer-pane.sh")
-                    .arg(&self.pdf_path)
-                    .spawn()?;
-                self.display_mode = DisplayMode::SplitScreen;
+                self.open_viuer_pane()?;
+            }
+        }
+        Ok(())
+    }
+
+    // Switch between the spatial and reflowed views, keeping the cursor on the same word
+    fn toggle_reflow(&mut self) -> Result<()> {
+        let anchor = self.word_at_cursor();
+
+        if self.display_mode == DisplayMode::SplitScreen {
+            self.toggle_zellij_pane()?;
+        }
+        self.display_mode = match self.display_mode {
+            DisplayMode::Reflow => DisplayMode::TextOnly,
+            _ => DisplayMode::Reflow,
+        };
+
+        self.clear_selection();
+        self.typing_run_end = None;
+        self.viewport_offset_x = 0;
+        self.viewport_offset_y = 0;
+        self.rebuild_text_buffer();
+
+        let placement = anchor.and_then(|(id, offset)| {
+            let placement = self.grid.placements.iter().find(|p| p.id == id)?;
+            Some((placement.row, placement.col + offset.min(placement.len.saturating_sub(1))))
+        });
+        match placement {
+            Some((row, col)) => self.reveal_grid_span(GridMatch { row, col, len: 1 }),
+            None => {
+                self.cursor_x = 0;
+                self.cursor_y = 0;
             }
         }
+
+        if self.search.is_some() {
+            self.refresh_search_matches();
+        }
+        Ok(())
+    }
+
+    // The word under the cursor, or the nearest one, and the cursor's column within it
+    fn word_at_cursor(&self) -> Option<(String, usize)> {
+        let (row, col) = self.cursor_grid_position();
+        self.grid.placements
+            .iter()
+            .min_by_key(|p| {
+                let dx = if col < p.col { p.col - col } else { col.saturating_sub(p.col + p.len - 1) };
+                (row.abs_diff(p.row), dx)
+            })
+            .map(|p| (p.id.clone(), col.saturating_sub(p.col).min(p.len.saturating_sub(1))))
+    }
+
+    // Reflowed text has no page positions to write edits back to
+    fn edit_blocked(&mut self) -> bool {
+        if self.display_mode != DisplayMode::Reflow {
+            return false;
+        }
+        self.notify(MessageLevel::Warning, "Reflow view is read-only; switch back to the spatial view to edit");
+        true
+    }
+
+    fn open_viuer_pane(&mut self) -> Result<()> {
+        std::process::Command::new("zellij")
+            .arg("action")
+            .arg("new-pane")
+            .arg("--direction")
+            .arg("left")
+            .arg("--")
+            .arg("/Users/jack/chonker95/viuer-pane.sh")
+            .arg(&self.pdf_path)
+            .spawn()?;
+        self.display_mode = DisplayMode::SplitScreen;
         Ok(())
     }
 
@@ -480,189 +1084,189 @@ impl WysiwygEditor {
 
     
     fn rebuild_text_buffer(&mut self) {
-        if self.elements.is_empty() {
-            self.text_buffer = String::new();
-            return;
-        }
-
-        // Build unlimited spatial grid and set viewport
+        // Build unlimited spatial grid and set viewport, reusing a prefetched layout if untouched
+        // Reflow wraps to the viewport, so it is laid out afresh every time
+        self.grid = if self.display_mode == DisplayMode::Reflow {
+            SpatialGrid::reflow(&self.elements, self.viewport_size().0, &self.config.layout)
+        } else {
+            match self.grid_cache.get(&self.current_page) {
+                Some(grid) if self.corrections.get(self.current_page).is_none() => grid.clone(),
+                _ => SpatialGrid::layout(&self.elements, &self.config.layout),
+            }
+        };
+        self.emphasis = self.page_emphasis();
         self.text_buffer = self.render_spatial_grid();
     }
 
-    fn render_spatial_grid(&mut self) -> String {
-        if self.elements.is_empty() {
-            return String::new();
-        }
+    // Classify words by their font against the page's body text size
+    fn page_emphasis(&self) -> HashMap<String, Emphasis> {
+        let Some(body_size) = body_font_size(&self.elements) else {
+            return HashMap::new();
+        };
 
-        // Find content bounds to determine required grid size
-        let min_x = self.elements.iter().map(|e| e.hpos).fold(f32::INFINITY, f32::min);
-        let max_x = self.elements.iter().map(|e| e.hpos + e.width).fold(f32::NEG_INFINITY, f32::max);
-        let min_y = self.elements.iter().map(|e| e.vpos).fold(f32::INFINITY, f32::min);
-        let max_y = self.elements.iter().map(|e| e.vpos + e.height).fold(f32::NEG_INFINITY, f32::max);
-
-        let content_width = (max_x - min_x).max(1.0);
-        let content_height = (max_y - min_y).max(1.0);
-
-        // Create unlimited grid based on actual content size
-        let scale_factor = 0.15; // Character scaling from PDF coordinates
-        self.grid_width = ((content_width * scale_factor) as usize + 50).max(300); // Minimum 300 cols
-        self.grid_height = ((content_height * scale_factor) as usize + 20).max(100); // Minimum 100 rows
-
-        self.content_grid = vec![vec![' '; self.grid_width]; self.grid_height];
-
-        // Place each element in the unlimited grid
-        for element in &self.elements {
-            let grid_x = ((element.hpos - min_x) * scale_factor) as usize;
-            let grid_y = ((element.vpos - min_y) * scale_factor) as usize;
-
-            // Place each character with proper Unicode width handling
-            let mut current_x = grid_x;
-            for ch in element.content.chars() {
-                let char_width = ch.width().unwrap_or(1);
-
-                // Check if character fits in grid
-                if current_x + char_width > self.grid_width {
-                    break;
-                }
-
-                let final_y = grid_y.min(self.grid_height - 1);
-
-                // Only place if grid position is empty (avoid overlaps)
-                if current_x < self.grid_width && final_y < self.grid_height && self.content_grid[final_y][current_x] == ' ' {
-                    self.content_grid[final_y][current_x] = ch;
+        self.elements
+            .iter()
+            .filter_map(|element| {
+                let emphasis = if element.is_heading(body_size) {
+                    Emphasis::Heading
+                } else if element.style.as_ref().is_some_and(|style| style.bold) {
+                    Emphasis::Bold
+                } else {
+                    return None;
+                };
+                Some((element.id.clone(), emphasis))
+            })
+            .collect()
+    }
 
-                    // Wide characters (CJK, emoji) need to mark next cell as occupied
-                    if char_width == 2 && current_x + 1 < self.grid_width {
-                        self.content_grid[final_y][current_x + 1] = '​'; // Zero-width space to mark continuation
-                    }
-                }
+    fn render_spatial_grid(&mut self) -> String {
+        if self.elements.is_empty() {
+            return String::new();
+        }
 
-                current_x += char_width;
-            }
+        let (viewport_width, viewport_height) = self.viewport_size();
+        for placement in &mut self.grid.placements {
+            placement.in_buffer = placement.row >= self.viewport_offset_y
+                && placement.row < self.viewport_offset_y + viewport_height
+                && placement.col >= self.viewport_offset_x
+                && placement.col + placement.len <= self.viewport_offset_x + viewport_width;
         }
 
         // Return viewport window of the large grid
         self.get_viewport_text()
     }
 
-    fn get_viewport_text(&self) -> String {
-        // Extract viewport window from the large content grid
-        let viewport_width = (self.terminal_width as usize).min(120);
-        let viewport_height = (self.terminal_height as usize - 2).min(50); // Reserve space for status
-
-        let mut result = String::new();
+    // Whole terminal minus the status line and gutter, capped only if the user asked
+    fn viewport_size(&self) -> (usize, usize) {
+        let mut viewport_width = self.terminal_width as usize;
+        let mut viewport_height = (self.terminal_height as usize).saturating_sub(2); // Reserve space for status
 
-        for screen_y in 0..viewport_height {
-            let grid_y = screen_y + self.viewport_offset_y;
+        if let Some(max_cols) = self.viewport_limits.max_cols {
+            viewport_width = viewport_width.min(max_cols);
+        }
+        if let Some(max_rows) = self.viewport_limits.max_rows {
+            viewport_height = viewport_height.min(max_rows);
+        }
+        (viewport_width, viewport_height)
+    }
 
-            if grid_y < self.grid_height {
-                let mut line = String::new();
+    // Move the viewport by a number of cells, staying inside the grid
+    fn scroll_viewport(&mut self, dx: isize, dy: isize) {
+        let (viewport_width, viewport_height) = self.viewport_size();
+        let max_x = self.grid.width.saturating_sub(viewport_width);
+        let max_y = self.grid.height.saturating_sub(viewport_height);
 
-                for screen_x in 0..viewport_width {
-                    let grid_x = screen_x + self.viewport_offset_x;
+        self.viewport_offset_x = self.viewport_offset_x.saturating_add_signed(dx).min(max_x);
+        self.viewport_offset_y = self.viewport_offset_y.saturating_add_signed(dy).min(max_y);
 
-                    if grid_x < self.grid_width {
-                        line.push(self.content_grid[grid_y][grid_x]);
-                    } else {
-                        line.push(' ');
-                    }
-                }
+        // The anchor is a screen position, so it would now point at different text
+        self.clear_selection();
 
-                result.push_str(&line.trim_end());
-                result.push('\n');
-            } else {
-                result.push('\n');
-            }
-        }
+        // Edits are already mapped onto elements, so the window can be rebuilt from them
+        self.rebuild_text_buffer();
+    }
 
-        result.trim_end().to_string()
+    fn get_viewport_text(&self) -> String {
+        // Extract viewport window from the large content grid
+        let (viewport_width, viewport_height) = self.viewport_size();
+        self.grid.viewport_text(self.viewport_offset_x, self.viewport_offset_y, viewport_width, viewport_height)
     }
-    
-    fn extract_alto_elements(&self) -> Result<Vec<AltoElement>> {
-        // Extract ONLY the current page to avoid overlay issues
-        let pdfium = Pdfium::default();
-        let document = pdfium.load_pdf_from_file(&self.pdf_path, None)?;
 
-        // Ensure we're only getting the specified page
-        let page_index = (self.current_page - 1) as u16;
-        if page_index >= document.pages().len() {
+    fn extract_alto_elements(&mut self) -> Result<Vec<AltoElement>> {
+        // Extract ONLY the current page to avoid overlay issues
+        if self.current_page > self.document.page_count() {
             return Ok(vec![]); // Page doesn't exist
         }
 
-        let page = document.pages().get(page_index)?;
-        let text_page = page.text()?;
-
-        let mut elements = Vec::new();
-        let page_height = page.height().value;
-        let page_width = page.width().value;
-
-        // For now, use segments which should work with the API
-        let mut segment_elements = Vec::new();
+        Ok(self.cached_page(self.current_page)?.elements.clone())
+    }
 
-        for (segment_idx, segment) in text_page.segments().iter().enumerate() {
-            let segment_text = segment.text();
-            let bounds = segment.bounds();
+    // Extract a page once and serve repeat visits from memory
+    fn cached_page(&mut self, number: u32) -> Result<&AltoPage> {
+        if !self.page_cache.contains_key(&number) {
+            let _ = self.show_loading_indicator(number);
+            let page = self.document.page(number)?;
+            self.evict_distant_pages();
+            self.page_cache.insert(number, page);
+        }
+        Ok(&self.page_cache[&number])
+    }
 
-            // Split segment into words while preserving positioning
-            let mut word_offset = 0.0;
-            let chars_in_segment = segment_text.len() as f32;
-            let avg_char_width = if chars_in_segment > 0.0 {
-                bounds.width().value / chars_in_segment
-            } else {
-                6.0
+    // Make room for one more page by dropping the cached pages farthest from the current one
+    fn evict_distant_pages(&mut self) {
+        let current = self.current_page;
+        while self.page_cache.len() >= Self::PAGE_CACHE_SIZE {
+            let Some(&farthest) = self.page_cache.keys().max_by_key(|number| number.abs_diff(current)) else {
+                break;
             };
+            self.page_cache.remove(&farthest);
+            self.grid_cache.remove(&farthest);
+        }
+    }
+
+    // Extract and lay out the next queued page that isn't cached yet
+    fn prefetch_next(&mut self) {
+        while let Some(number) = self.prefetcher.next() {
+            if self.page_cache.contains_key(&number) {
+                continue;
+            }
 
-            for word in segment_text.split_whitespace() {
-                if !word.is_empty() {
-                    segment_elements.push(AltoElement::new(
-                        format!("seg_{}_{}", segment_idx, segment_elements.len()),
-                        word.to_string(),
-                        bounds.left().value + word_offset,
-                        page_height - bounds.top().value, // Flip Y coordinate
-                        word.len() as f32 * avg_char_width,
-                        bounds.height().value,
-                    ));
-
-                    word_offset += (word.len() + 1) as f32 * avg_char_width;
+            // A failed prefetch is retried when the page is visited
+            match self.document.page(number) {
+                Ok(page) => {
+                    let grid = SpatialGrid::layout(&page.elements, &self.config.layout);
+                    self.evict_distant_pages();
+                    self.page_cache.insert(number, page);
+                    self.grid_cache.insert(number, grid);
                 }
+                Err(e) => log::warn!("prefetch of page {} failed: {}", number, e),
             }
+            return;
         }
+    }
 
-        // Use segments if available, otherwise fallback
-        let char_positions = segment_elements;
-
-        // Return the elements from segments
-        elements = char_positions;
-
-        // Fallback if character extraction fails
-        if elements.is_empty() {
-            let text_content = page.text()?.all();
-            let mut y_pos = 0.0;
+    // Queue the previous and next pages so Ctrl+Left/Right don't block
+    fn prefetch_adjacent_pages(&mut self) {
+        let page_count = self.document.page_count();
+        let neighbours = [self.current_page.saturating_sub(1), self.current_page + 1];
 
-            for line in text_content.lines() {
-                let mut x_pos = 0.0;
-                for word in line.split_whitespace() {
-                    if !word.is_empty() {
-                        elements.push(AltoElement::new(
-                            format!("word_{}", elements.len()),
-                            word.to_string(),
-                            x_pos * 8.0,
-                            y_pos * 12.0,
-                            word.len() as f32 * 8.0,
-                            12.0,
-                        ));
-                        x_pos += word.len() as f32 + 1.0;
-                    }
-                }
-                y_pos += 1.0;
+        self.prefetcher.clear();
+        for number in neighbours {
+            if number >= 1 && number <= page_count && !self.page_cache.contains_key(&number) {
+                self.prefetcher.request(number);
             }
         }
+    }
 
-        Ok(elements)
+    fn show_loading_indicator(&mut self, number: u32) -> Result<()> {
+        execute!(
+            io::stdout(),
+            cursor::MoveTo(0, self.terminal_height.saturating_sub(1)),
+            Clear(ClearType::CurrentLine),
+            SetForegroundColor(Color::Yellow),
+            Print(format!("Chonker95 - Loading page {}...", number)),
+            ResetColor
+        )?;
+        io::stdout().flush()?;
+
+        // Drawn outside the frame model, so the status row must be repainted next frame
+        self.screen.invalidate_row(self.terminal_height.saturating_sub(1));
+        Ok(())
+    }
+
+    // Extract every page of the document, reusing pages already cached
+    fn extract_all_pages(&mut self) -> Result<Vec<AltoPage>> {
+        (1..=self.document.page_count())
+            .map(|number| self.cached_page(number).cloned())
+            .collect()
+    }
+
+    // Page dimensions for ALTO output of the current page
+    fn current_page_size(&mut self) -> Result<(f32, f32)> {
+        let page = self.cached_page(self.current_page)?;
+        Ok((page.width, page.height))
     }
 
-    
-    
     // Enhanced terminal state management to prevent ANSI hell
     fn ensure_clean_state(&mut self) -> Result<()> {
         if !self.terminal_state_clean {
@@ -679,24 +1283,36 @@ impl WysiwygEditor {
     }
 
     fn render(&mut self) -> Result<()> {
-        // Ensure clean state before every render to prevent ANSI hell
+        // A dirty terminal gets a full clear and repaint; otherwise only changed cells are drawn
+        if !self.terminal_state_clean {
+            self.screen.invalidate();
+        }
         self.ensure_clean_state()?;
 
-        execute!(io::stdout(), Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+        let mut frame = Frame::new(self.terminal_width, self.terminal_height);
 
         // Always just render text - Zellij handles the pane management
-        self.render_text_only()?;
+        self.render_text_only(&mut frame);
+        let results_cursor = self.render_search_results(&mut frame);
+        let history_open = self.render_message_history(&mut frame);
 
         // Show status line with selection info
-        let selection_info = if self.is_all_selected {
-            " | ALL SELECTED"
-        } else {
-            ""
+        let selection_info = match (self.selection_range(), self.block_selection) {
+            (Some(((start_row, _), (end_row, _))), true) => {
+                let (left, right) = self.block_columns().unwrap_or((0, 0));
+                format!(" | {}x{} BLOCK", end_row - start_row + 1, right - left)
+            }
+            (Some(_), false) => {
+                let count = self.selected_text().map_or(0, |text| text.chars().count());
+                format!(" | {} SELECTED", count)
+            }
+            (None, _) => String::new(),
         };
 
         let mode_info = match self.display_mode {
             DisplayMode::TextOnly => "",
             DisplayMode::SplitScreen => " | PDF OPEN",
+            DisplayMode::Reflow => " | REFLOW",
         };
 
         // Enhanced status line with Kitty-specific info
@@ -706,77 +1322,232 @@ impl WysiwygEditor {
             String::new()
         };
 
-        let cmd_a_text = match self.display_mode {
-            DisplayMode::TextOnly => "A:open-pdf",
-            DisplayMode::SplitScreen => "A:close-pdf",
+        let pdf_pane_text = match self.display_mode {
+            DisplayMode::TextOnly | DisplayMode::Reflow => "P:open-pdf",
+            DisplayMode::SplitScreen => "P:close-pdf",
         };
 
-        execute!(
-            io::stdout(),
-            cursor::MoveTo(0, self.terminal_height - 1),
-            SetForegroundColor(Color::Yellow),
-            Print(format!("Chonker95{} - {} - Page {}{}{} | {} | Cmd+{} S:save W:close Q:quit",
-                terminal_info,
-                self.pdf_path.file_name().unwrap_or_default().to_string_lossy(),
-                self.current_page,
-                mode_info,
-                selection_info,
-                self.get_mac_shortcut_text(),
-                cmd_a_text)),
-            ResetColor
-        )?;
+        // Show the PDF's own label too when it differs from the number
+        let page_label = match &self.page_label {
+            Some(label) if *label != self.current_page.to_string() => format!(" ({})", label),
+            _ => String::new(),
+        };
 
-        // Position cursor with extra safety for Kitty
-        let cursor_x = self.cursor_x;
+        let location = format!("Chonker95{} - {} - Page {}/{}{}{}{}",
+            terminal_info,
+            self.pdf_path.file_name().unwrap_or_default().to_string_lossy(),
+            self.current_page,
+            self.document.page_count(),
+            page_label,
+            mode_info,
+            selection_info);
+        let shortcuts = format!(" | {} | Cmd+{} S:save E:alto W:close Q:quit",
+            self.get_mac_shortcut_text(),
+            pdf_pane_text);
+        let status_row = self.terminal_height.saturating_sub(1);
+
+        // A prompt takes over the status line and the cursor
+        let (cursor_x, cursor_y) = match self.page_prompt_line().or_else(|| self.search_prompt_line()) {
+            Some((prompt, prompt_cursor)) => {
+                frame.put_str(0, status_row, &prompt, self.config.colors.prompt, Color::Reset);
+                (prompt_cursor, status_row)
+            }
+            None => {
+                frame.put_str(0, status_row, &location, self.config.colors.status, Color::Reset);
+
+                // A fresh message replaces the shortcut hints until it expires
+                let after = location.width() as u16;
+                match self.messages.current() {
+                    Some(message) => {
+                        let text = format!(" | {}", message.text);
+                        frame.put_str(after, status_row, &text, message.level.color(), Color::Reset);
+                    }
+                    None => frame.put_str(after, status_row, &shortcuts, self.config.colors.status, Color::Reset),
+                }
+                (self.cursor_x, self.cursor_y)
+            }
+        };
+        let (cursor_x, cursor_y) = match (history_open, results_cursor) {
+            (true, _) => (0, 0),
+            (false, Some(results)) => results,
+            (false, None) => (cursor_x, cursor_y),
+        };
 
-        execute!(
-            io::stdout(),
-            cursor::MoveTo(cursor_x, self.cursor_y),
+        // Hide the cursor while cells change so it doesn't flicker across the screen
+        let mut stdout = io::stdout();
+        queue!(stdout, cursor::Hide)?;
+        self.screen.draw(frame, &mut stdout)?;
+
+        queue!(
+            stdout,
+            cursor::MoveTo(cursor_x, cursor_y),
             cursor::Show
         )?;
 
-        io::stdout().flush()?;
+        stdout.flush()?;
         Ok(())
     }
-    
-    fn render_text_only(&self) -> Result<()> {
+
+    // Draw the document search results over the text area; returns where the cursor goes
+    fn render_search_results(&self, frame: &mut Frame) -> Option<(u16, u16)> {
+        let results = self.search_results.as_ref()?;
+        let rows = self.search_results_rows();
+        let width = self.terminal_width as usize;
+
+        let pages = results.hits.iter().map(|hit| hit.page).collect::<HashSet<_>>().len();
+        let header = format!(
+            " Document search \"{}\": {} hits on {} pages  (Enter:go Esc:close)",
+            results.phrase,
+            results.hits.len(),
+            pages
+        );
+        frame.put_str(0, 0, &format!("{:<width$}", header), Color::Black, Color::Cyan);
+
+        for row in 0..rows {
+            let line = match results.hits.get(results.scroll + row) {
+                Some(hit) => format!(" p.{:<5} {}", hit.page, hit.context),
+                None => String::new(),
+            };
+            let line: String = format!("{:<width$}", line).chars().take(width).collect();
+            let (fg, bg) = if results.scroll + row == results.selected {
+                (Color::White, Color::Blue)
+            } else {
+                (Color::Reset, Color::Reset)
+            };
+            frame.put_str(0, row as u16 + 1, &line, fg, bg);
+        }
+
+        Some((0, (results.selected - results.scroll) as u16 + 1))
+    }
+
+    // Hit rows that fit between the results header and the status line
+    fn search_results_rows(&self) -> usize {
+        (self.terminal_height as usize).saturating_sub(2).max(1)
+    }
+
+    fn render_text_only(&self, frame: &mut Frame) {
         // Display the text buffer with selection highlighting
         let lines: Vec<&str> = self.text_buffer.lines().collect();
+        let emphasized: Vec<(&GridPlacement, Emphasis)> = self.grid.placements.iter()
+            .filter(|placement| placement.in_buffer)
+            .filter_map(|placement| Some((placement, *self.emphasis.get(&placement.id)?)))
+            .collect();
         for (i, line) in lines.iter().enumerate() {
-            if i < (self.terminal_height - 2) as usize {
-                execute!(io::stdout(), cursor::MoveTo(0, i as u16))?;
-
-                if self.is_all_selected {
-                    // Highlight entire line if everything is selected
-                    execute!(
-                        io::stdout(),
-                        SetBackgroundColor(Color::Blue),
-                        SetForegroundColor(Color::White),
-                        Print(line),
-                        ResetColor
-                    )?;
-                } else {
-                    execute!(io::stdout(), Print(line))?;
+            if i < self.terminal_height.saturating_sub(2) as usize {
+                frame.put_str(0, i as u16, line, Color::Reset, Color::Reset);
+
+                // Headings and bold words keep their weight from the PDF
+                let row = i + self.viewport_offset_y;
+                for (placement, emphasis) in emphasized.iter().filter(|(p, _)| p.row == row) {
+                    let fg = match emphasis {
+                        Emphasis::Heading => Some(self.config.colors.heading),
+                        Emphasis::Bold => None,
+                    };
+                    frame.emphasize((placement.col - self.viewport_offset_x) as u16, i as u16, placement.len, fg);
+                }
+
+                // Overdraw search matches on this line, the current one stronger
+                if let Some(search) = &self.search {
+                    let row = i + self.viewport_offset_y;
+                    for (index, found) in search.matches.iter().enumerate() {
+                        if found.row != row || found.col < self.viewport_offset_x {
+                            continue;
+                        }
+                        let from = found.col - self.viewport_offset_x;
+                        let text: String = line.chars().skip(from).take(found.len).collect();
+                        let bg = if search.current == Some(index) {
+                            self.config.colors.current_match_bg
+                        } else {
+                            self.config.colors.match_bg
+                        };
+                        frame.put_str(from as u16, i as u16, &text, Color::Black, bg);
+                    }
+                }
+
+                // Overdraw the selected part of this line
+                if let Some((from, to)) = self.selection_span(i) {
+                    let mut selected: String = line.chars().take(to).skip(from).collect();
+                    if self.block_selection {
+                        // Keep the rectangle visible past short lines
+                        let width = to - from;
+                        selected.extend(std::iter::repeat_n(' ', width.saturating_sub(selected.chars().count())));
+                    }
+                    frame.put_str(from as u16, i as u16, &selected, self.config.colors.selection_fg, self.config.colors.selection_bg);
                 }
             }
         }
-        Ok(())
     }
 
-    
-    
-    
     fn handle_mouse_click(&mut self, x: u16, y: u16) -> Result<()> {
         // Allow cursor to go anywhere, even beyond viewport
-        if y < self.terminal_height - 1 { // Only avoid status line
+        if y < self.terminal_height.saturating_sub(1) { // Only avoid status line
             self.cursor_x = x;
             self.cursor_y = y;
             // No bounds checking - cursor can go anywhere, even off-screen
+
+            // Repeated clicks on the same cell select the word, then the line
+            let clicks = match self.last_click {
+                Some((at, last_x, last_y, count))
+                    if at.elapsed() < Duration::from_millis(400) && (last_x, last_y) == (x, y) => count % 3 + 1,
+                _ => 1,
+            };
+            self.last_click = Some((Instant::now(), x, y, clicks));
+
+            match clicks {
+                2 => self.select_word_at_cursor(),
+                3 => self.select_line_at_cursor(),
+                // A plain click starts a potential drag selection
+                _ => self.selection_anchor = Some((x, y)),
+            }
         }
         
         Ok(())
     }
+
+    fn handle_mouse_drag(&mut self, x: u16, y: u16) -> Result<()> {
+        if y < self.terminal_height.saturating_sub(1) {
+            if self.selection_anchor.is_none() {
+                self.selection_anchor = Some((self.cursor_x, self.cursor_y));
+            }
+            self.cursor_x = x;
+            self.cursor_y = y;
+        }
+        Ok(())
+    }
+
+    fn handle_mouse_event(&mut self, event: MouseEvent) -> Result<()> {
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                self.handle_mouse_click(event.column, event.row)?;
+                // Alt+drag selects a rectangular block
+                self.block_selection = event.modifiers.contains(KeyModifiers::ALT);
+                Ok(())
+            }
+            MouseEventKind::Drag(MouseButton::Left) => self.handle_mouse_drag(event.column, event.row),
+            _ => Ok(()),
+        }
+    }
     
+    // Re-fit the viewport to a new terminal size (e.g. a resized Zellij pane)
+    fn handle_resize(&mut self, width: u16, height: u16) -> Result<()> {
+        self.terminal_width = width;
+        self.terminal_height = height;
+
+        // Keep the viewport inside the grid and the cursor inside the viewport
+        let (viewport_width, viewport_height) = self.viewport_size();
+        self.viewport_offset_x = self.viewport_offset_x.min(self.grid.width.saturating_sub(viewport_width));
+        self.viewport_offset_y = self.viewport_offset_y.min(self.grid.height.saturating_sub(viewport_height));
+        self.cursor_x = self.cursor_x.min(viewport_width.saturating_sub(1) as u16);
+        self.cursor_y = self.cursor_y.min(viewport_height.saturating_sub(1) as u16);
+
+        // Corrections live in the elements, so re-laying out keeps edits
+        self.rebuild_text_buffer();
+
+        // Old frame contents are garbage at the new size; force a full repaint
+        self.terminal_state_clean = false;
+        Ok(())
+    }
+
     // Enhanced key handling with Kitty-specific quirks
     fn normalize_key_for_terminal(&self, key: KeyCode, modifiers: KeyModifiers) -> (KeyCode, KeyModifiers) {
         if self.terminal_info.is_kitty {
@@ -802,12 +1573,49 @@ impl WysiwygEditor {
     }
 
     fn handle_key_input(&mut self, key: KeyCode, modifiers: KeyModifiers) -> Result<bool> {
-        // Mark state as potentially dirty after key input
-        self.terminal_state_clean = false;
-
         // Normalize key for terminal-specific quirks
         let (normalized_key, normalized_modifiers) = self.normalize_key_for_terminal(key, modifiers);
 
+        // Prompts and overlays get every key while open
+        if self.message_history.is_some() {
+            self.handle_message_history_key(normalized_key, normalized_modifiers);
+            return Ok(false);
+        }
+        if self.page_prompt.is_some() {
+            self.handle_page_prompt_key(normalized_key)?;
+            return Ok(false);
+        }
+        if self.search_results.is_some() {
+            self.handle_search_results_key(normalized_key)?;
+            return Ok(false);
+        }
+        if self.search.is_some() {
+            self.handle_search_key(normalized_key, normalized_modifiers)?;
+            return Ok(false);
+        }
+
+        // Bound commands win over movement and typing
+        if let Some(action) = self.config.keys.action_for(normalized_key, normalized_modifiers) {
+            return self.run_action(action);
+        }
+
+        // Shift extends the selection from where the cursor was; plain movement drops it
+        let is_navigation = matches!(
+            normalized_key,
+            KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right | KeyCode::Home | KeyCode::End
+        );
+        if is_navigation {
+            if normalized_modifiers.contains(KeyModifiers::SHIFT) {
+                if self.selection_anchor.is_none() {
+                    self.selection_anchor = Some((self.cursor_x, self.cursor_y));
+                    // Alt+Shift+arrows select a rectangular block
+                    self.block_selection = normalized_modifiers.contains(KeyModifiers::ALT);
+                }
+            } else {
+                self.clear_selection();
+            }
+        }
+
         match normalized_key {
             // Cursor movement
             KeyCode::Up => {
@@ -832,27 +1640,11 @@ impl WysiwygEditor {
                 }
             }
             // Viewport scrolling controls
-            KeyCode::Left if normalized_modifiers.contains(KeyModifiers::ALT) => {
-                self.viewport_offset_x = self.viewport_offset_x.saturating_sub(10);
-            }
-            KeyCode::Right if normalized_modifiers.contains(KeyModifiers::ALT) => {
-                self.viewport_offset_x = (self.viewport_offset_x + 10).min(self.grid_width.saturating_sub(50));
-            }
-            KeyCode::Left if normalized_modifiers.contains(KeyModifiers::CONTROL) => {
-                // Ctrl+Left: Previous page
-                if self.current_page > 1 {
-                    self.current_page -= 1;
-                    self.load_page()?;
-                    // Sync page change to other pane
-                    let _ = self.sync_manager.send_message(SyncMessage::PageChange(self.current_page));
-                }
+            KeyCode::Left if normalized_modifiers.contains(KeyModifiers::ALT) && !normalized_modifiers.contains(KeyModifiers::SHIFT) => {
+                self.scroll_viewport(-10, 0);
             }
-            KeyCode::Right if normalized_modifiers.contains(KeyModifiers::CONTROL) => {
-                // Ctrl+Right: Next page
-                self.current_page += 1;
-                self.load_page()?;
-                // Sync page change to other pane
-                let _ = self.sync_manager.send_message(SyncMessage::PageChange(self.current_page));
+            KeyCode::Right if normalized_modifiers.contains(KeyModifiers::ALT) && !normalized_modifiers.contains(KeyModifiers::SHIFT) => {
+                self.scroll_viewport(10, 0);
             }
             KeyCode::Left => {
                 if self.cursor_x > 0 {
@@ -910,10 +1702,6 @@ impl WysiwygEditor {
                     self.cursor_x = 0; // Empty line
                 }
             }
-            // Toggle Zellij pane with Ctrl+P (handle BEFORE text input)
-            KeyCode::Char('p') | KeyCode::Char('P') if normalized_modifiers.contains(KeyModifiers::CONTROL) => {
-                self.toggle_zellij_pane()?;
-            }
 
             // Text editing (Mac-aware)
             KeyCode::Char(c) if !self.is_mac_modifier(normalized_modifiers) && !normalized_modifiers.contains(KeyModifiers::CONTROL) => {
@@ -922,53 +1710,646 @@ impl WysiwygEditor {
             KeyCode::Backspace => {
                 self.delete_char_at_cursor()?;
             }
+            KeyCode::Delete => {
+                // Only clears a selection; there is no forward delete
+                if self.selection_range().is_some() && !self.edit_blocked() {
+                    self.record_edit();
+                    self.delete_selection();
+                }
+            }
             KeyCode::Enter => {
-                self.insert_char_at_cursor('\n')?;
+                // Words keep their page positions, so a line break would be undone by the next relayout
+                if !self.edit_blocked() {
+                    self.notify(MessageLevel::Warning, "Line breaks can't be inserted; words stay on their line of the page");
+                }
             }
-            KeyCode::Char('c') | KeyCode::Char('C') if self.is_mac_modifier(normalized_modifiers) => {
-                self.copy_selection()?;
+            KeyCode::Esc => {
+                self.clear_selection();
             }
-            KeyCode::Char('x') | KeyCode::Char('X') if self.is_mac_modifier(normalized_modifiers) => {
-                self.cut_selection()?;
+
+            // Additional viewport scrolling
+            KeyCode::PageUp => {
+                self.scroll_viewport(0, -10);
             }
-            KeyCode::Char('v') | KeyCode::Char('V') if self.is_mac_modifier(normalized_modifiers) => {
-                self.paste_from_clipboard()?;
+            KeyCode::PageDown => {
+                self.scroll_viewport(0, 10);
             }
+            _ => {}
+        }
+        
+        Ok(false)
+    }
+
+    // Commands bound through the keymap; true means quit
+    fn run_action(&mut self, action: Action) -> Result<bool> {
+        match action {
+            // Mac-specific file operations
+            Action::Save => self.save_extracted_text()?,
+            Action::ExportPage => self.export_alto_page()?,
+            Action::ExportDocument => self.export_alto_document()?,
+            Action::SaveSession => {
+                // TODO: Implement file picker for opening new PDFs
+                // For now, just save session state
+                match self.save_session_state() {
+                    Ok(()) => self.notify(MessageLevel::Info, "Session saved"),
+                    Err(e) => self.notify(MessageLevel::Error, format!("Couldn't save session: {}", e)),
+                }
+            }
+            // Mac convention: Cmd+W closes window, Cmd+Q quits app
+            Action::Close | Action::Quit => return Ok(true),
+
+            Action::SelectAll => self.select_all(),
+            Action::Copy => self.copy_selection()?,
+            Action::CopyTsv => self.copy_selection_as_tsv()?,
+            Action::Cut => self.cut_selection()?,
+            Action::Paste => self.paste_from_clipboard()?,
+            Action::Undo => self.undo(),
+            Action::Redo => self.redo(),
+
+            Action::Find => self.open_search(false),
+            Action::Replace => self.open_search(true),
+            Action::GoToPage => self.page_prompt = Some(String::new()),
+            Action::FirstPage => self.go_to_page(1)?,
+            Action::LastPage => self.go_to_page(self.document.page_count())?,
+            Action::PreviousPage => self.go_to_page(self.current_page.saturating_sub(1))?,
+            Action::NextPage => self.go_to_page(self.current_page + 1)?,
+            Action::TogglePdf => {
+                // The PDF pane is for checking the spatial layout, so leave reflow first
+                if self.display_mode == DisplayMode::Reflow {
+                    self.toggle_reflow()?;
+                }
+                self.toggle_zellij_pane()?
+            }
+            Action::ToggleReflow => self.toggle_reflow()?,
+            Action::Messages => self.message_history = Some(0),
+        }
+        Ok(false)
+    }
+    
+    fn notify(&mut self, level: MessageLevel, text: impl Into<String>) {
+        let text = text.into();
+        match level {
+            MessageLevel::Info => log::info!("{}", text),
+            MessageLevel::Warning => log::warn!("{}", text),
+            MessageLevel::Error => log::error!("{}", text),
+        }
+        self.messages.push(level, text);
+    }
+
+    fn handle_message_history_key(&mut self, key: KeyCode, modifiers: KeyModifiers) {
+        let closes = key == KeyCode::Esc || self.config.keys.action_for(key, modifiers) == Some(Action::Messages);
+        let last = self.messages.history.len().saturating_sub(1);
+        let Some(scroll) = self.message_history.as_mut() else {
+            return;
+        };
+
+        match key {
+            _ if closes => self.message_history = None,
+            KeyCode::Up => *scroll = scroll.saturating_sub(1),
+            KeyCode::Down => *scroll = (*scroll + 1).min(last),
+            KeyCode::Home => *scroll = 0,
+            KeyCode::End => *scroll = last,
+            _ => {}
+        }
+    }
+
+    // Newest messages first over the text area, with how long ago each was posted
+    fn render_message_history(&self, frame: &mut Frame) -> bool {
+        let Some(scroll) = self.message_history else {
+            return false;
+        };
+        let width = self.terminal_width as usize;
+        let rows = (self.terminal_height as usize).saturating_sub(2);
+
+        let header = format!(" Messages ({})  (Up/Down:scroll Esc:close)", self.messages.history.len());
+        frame.put_str(0, 0, &format!("{:<width$}", header), Color::Black, Color::Cyan);
+
+        let mut newest_first = self.messages.history.iter().rev().skip(scroll);
+        for row in 0..rows {
+            let (line, color) = match newest_first.next() {
+                Some(message) => {
+                    let age = message.at.elapsed().as_secs();
+                    let age = if age < 60 { format!("{}s", age) } else { format!("{}m", age / 60) };
+                    (format!(" {:>4} ago  {:<5}  {}", age, message.level.tag(), message.text), message.level.color())
+                }
+                None => (String::new(), Color::Reset),
+            };
+            let line: String = format!("{:<width$}", line).chars().take(width).collect();
+            frame.put_str(0, row as u16 + 1, &line, color, Color::Reset);
+        }
+        true
+    }
+
+    // Switch pages, clamped to the document, and tell the other pane
+    fn go_to_page(&mut self, number: u32) -> Result<()> {
+        let number = number.clamp(1, self.document.page_count().max(1));
+        if number == self.current_page {
+            return Ok(());
+        }
+
+        self.current_page = number;
+        self.load_page()?;
+        if let Err(e) = self.sync_manager.send_message(SyncMessage::PageChange(self.current_page)) {
+            self.notify(MessageLevel::Warning, format!("Couldn't sync page to the other pane: {}", e));
+        }
+        Ok(())
+    }
+
+    fn handle_page_prompt_key(&mut self, key: KeyCode) -> Result<()> {
+        let Some(input) = self.page_prompt.as_mut() else {
+            return Ok(());
+        };
+
+        match key {
+            KeyCode::Esc => self.page_prompt = None,
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Enter => {
+                let input = input.trim().to_string();
+                if let Some(number) = self.resolve_page(&input) {
+                    self.page_prompt = None;
+                    self.go_to_page(number)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    // A page number, or failing that a page label like "iv"
+    fn resolve_page(&self, input: &str) -> Option<u32> {
+        let page_count = self.document.page_count();
+        if let Ok(number) = input.parse::<u32>() {
+            return (1..=page_count).contains(&number).then_some(number);
+        }
+
+        (1..=page_count).find(|&number| {
+            self.document
+                .page_label(number)
+                .is_some_and(|label| label.eq_ignore_ascii_case(input))
+        })
+    }
+
+    fn open_search(&mut self, replacing: bool) {
+        // Reopening keeps the last query so Ctrl+F, Enter repeats a search
+        let mut prompt = self.search.take().unwrap_or_default();
+        prompt.replacing = replacing;
+        prompt.editing_replacement = false;
+        prompt.message = None;
+        self.search = Some(prompt);
+        self.clear_selection();
+        self.refresh_search_matches();
+    }
+
+    fn handle_search_key(&mut self, key: KeyCode, modifiers: KeyModifiers) -> Result<()> {
+        let Some(search) = self.search.as_mut() else {
+            return Ok(());
+        };
+        search.message = None;
+
+        match key {
             KeyCode::Esc => {
-                self.clear_selection();
+                self.search = None;
+            }
+            // Alt+R toggles regex, Alt+C case sensitivity, Alt+A replaces every match on the page
+            KeyCode::Char('r') | KeyCode::Char('R') if modifiers.contains(KeyModifiers::ALT) => {
+                search.query.regex = !search.query.regex;
+                self.refresh_search_matches();
+            }
+            KeyCode::Char('c') | KeyCode::Char('C') if modifiers.contains(KeyModifiers::ALT) => {
+                search.query.case_sensitive = !search.query.case_sensitive;
+                self.refresh_search_matches();
+            }
+            KeyCode::Char('a') | KeyCode::Char('A') if modifiers.contains(KeyModifiers::ALT) && search.replacing => {
+                self.replace_all_on_page();
+            }
+            // Alt+D lists the query's hits across the whole document
+            KeyCode::Char('d') | KeyCode::Char('D') if modifiers.contains(KeyModifiers::ALT) => {
+                self.search_document()?;
+            }
+            KeyCode::Tab if search.replacing => {
+                search.editing_replacement = !search.editing_replacement;
+            }
+            KeyCode::Enter if search.editing_replacement => {
+                self.replace_current_match()?;
+            }
+            KeyCode::Enter if !modifiers.contains(KeyModifiers::SHIFT) => {
+                self.find_next(true, false)?;
+            }
+            KeyCode::Down => {
+                self.find_next(true, false)?;
+            }
+            KeyCode::Enter | KeyCode::Up => {
+                self.find_next(false, false)?;
+            }
+            KeyCode::Backspace => {
+                if search.editing_replacement {
+                    search.replacement.pop();
+                } else {
+                    search.query.pattern.pop();
+                    self.find_next(true, true)?;
+                }
+            }
+            KeyCode::Char(c) if !modifiers.contains(KeyModifiers::CONTROL) => {
+                if search.editing_replacement {
+                    search.replacement.push(c);
+                } else {
+                    // Incremental: stay on the current match while it still matches
+                    search.query.pattern.push(c);
+                    self.find_next(true, true)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn page_prompt_line(&self) -> Option<(String, u16)> {
+        let input = self.page_prompt.as_ref()?;
+
+        let prompt = format!("Go to page (1-{}): ", self.document.page_count());
+        let cursor = prompt.width() + input.width();
+        // Labels are only looked up on Enter, since that loads every page
+        let out_of_range = input
+            .trim()
+            .parse::<u32>()
+            .is_ok_and(|number| number == 0 || number > self.document.page_count());
+        let invalid = if out_of_range { "  (no such page)" } else { "" };
+        Some((format!("{}{}{}  Enter:go Esc:cancel", prompt, input, invalid), cursor as u16))
+    }
+
+    // Status-line text for the open prompt and the column its cursor sits at
+    fn search_prompt_line(&self) -> Option<(String, u16)> {
+        let search = self.search.as_ref()?;
+
+        let mut line = format!(
+            "Find [{}{}]: {}",
+            if search.query.regex { ".*" } else { "  " },
+            if search.query.case_sensitive { "Aa" } else { "  " },
+            search.query.pattern
+        );
+        let mut cursor = line.width();
+
+        if search.replacing {
+            line.push_str(" | Replace: ");
+            line.push_str(&search.replacement);
+            if search.editing_replacement {
+                cursor = line.width();
+            }
+        }
+
+        let counter = match (search.current, search.matches.len()) {
+            (_, 0) if search.query.pattern.is_empty() => String::new(),
+            (_, 0) => "no matches on page".to_string(),
+            (Some(index), total) => format!("{}/{}", index + 1, total),
+            (None, total) => format!("{} on page", total),
+        };
+        line.push_str(&format!("  ({})", search.message.as_deref().unwrap_or(&counter)));
+
+        let help = if search.replacing {
+            "  Enter:next/replace Tab:field Alt+A:all Alt+D:document Alt+R:regex Alt+C:case Esc:close"
+        } else {
+            "  Enter:next Shift+Enter:prev Alt+D:document Alt+R:regex Alt+C:case Esc:close"
+        };
+        line.push_str(help);
+
+        Some((line, cursor as u16))
+    }
+
+    // Re-run the query against the current page's (corrected) grid
+    fn refresh_search_matches(&mut self) {
+        let Some(search) = self.search.as_ref() else {
+            return;
+        };
+        if search.query.pattern.is_empty() {
+            let search = self.search.as_mut().unwrap();
+            search.matches.clear();
+            search.current = None;
+            return;
+        }
+
+        let compiled = search.query.compile();
+
+        // In-place edits only move placements, so lay the grid out again before reading its cells
+        self.rebuild_text_buffer();
+        let cursor = self.cursor_grid_position();
+
+        let search = self.search.as_mut().unwrap();
+        match compiled {
+            Ok(re) => {
+                search.matches = find_in_grid(&self.grid, &re);
+                search.current = search.matches.iter().position(|m| (m.row, m.col) == cursor);
+            }
+            Err(e) => {
+                search.matches.clear();
+                search.current = None;
+                search.message = Some(format!("bad pattern: {}", e));
+            }
+        }
+    }
+
+    fn cursor_grid_position(&self) -> (usize, usize) {
+        (
+            self.cursor_y as usize + self.viewport_offset_y,
+            self.cursor_x as usize + self.viewport_offset_x,
+        )
+    }
+
+    // Matches on another page, from its extraction with that page's corrections applied
+    fn page_matches(&mut self, number: u32, query: &SearchQuery) -> Result<Vec<GridMatch>> {
+        let re = query.compile()?;
+        let original = self.cached_page(number)?.elements.clone();
+        let elements = match self.corrections.get(number) {
+            Some(corrections) => corrections.apply(&original),
+            None => original,
+        };
+        Ok(find_in_grid(&SpatialGrid::layout(&elements, &self.config.layout), &re))
+    }
+
+    // Move to the next (or previous) match, going on to other pages when this one runs out;
+    // while the query is being typed, the current match counts and only this page is searched
+    fn find_next(&mut self, forward: bool, incremental: bool) -> Result<()> {
+        self.refresh_search_matches();
+        let Some(search) = self.search.as_ref() else {
+            return Ok(());
+        };
+        if search.query.pattern.is_empty() || search.query.compile().is_err() {
+            return Ok(());
+        }
+
+        let cursor = self.cursor_grid_position();
+        let on_page = if forward {
+            search.matches.iter().position(|m| {
+                let at = (m.row, m.col);
+                at > cursor || (incremental && at == cursor)
+            })
+        } else {
+            search.matches.iter().rposition(|m| (m.row, m.col) < cursor)
+        };
+        if let Some(index) = on_page {
+            self.jump_to_match(index);
+            return Ok(());
+        }
+
+        // Extract ahead through the rest of the document, wrapping around; not per keystroke,
+        // since that lays out every page and jumps away mid-typing
+        if !incremental {
+            let query = search.query.clone();
+            let page_count = self.document.page_count();
+            for step in 1..page_count {
+                let number = if forward {
+                    (self.current_page - 1 + step) % page_count + 1
+                } else {
+                    (self.current_page - 1 + page_count - step) % page_count + 1
+                };
+
+                if !self.page_matches(number, &query)?.is_empty() {
+                    self.go_to_page(number)?;
+                    self.refresh_search_matches();
+                    let matches = self.search.as_ref().map_or(0, |s| s.matches.len());
+                    self.jump_to_match(if forward { 0 } else { matches.saturating_sub(1) });
+                    return Ok(());
+                }
+            }
+        }
+
+        // Only this page matches (or only this page was searched), so wrap within it
+        let matches = self.search.as_ref().map_or(0, |s| s.matches.len());
+        if matches > 0 {
+            self.jump_to_match(if forward { 0 } else { matches - 1 });
+            if let Some(search) = self.search.as_mut() {
+                search.message = Some("wrapped".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    // Scroll the viewport so the match is fully visible and put the cursor on it
+    fn jump_to_match(&mut self, index: usize) {
+        let Some(found) = self.search.as_ref().and_then(|s| s.matches.get(index)).copied() else {
+            return;
+        };
+        self.reveal_grid_span(found);
+        if let Some(search) = self.search.as_mut() {
+            search.current = Some(index);
+        }
+    }
+
+    fn reveal_grid_span(&mut self, found: GridMatch) {
+        let (viewport_width, viewport_height) = self.viewport_size();
+
+        let visible = found.row >= self.viewport_offset_y
+            && found.row < self.viewport_offset_y + viewport_height
+            && found.col >= self.viewport_offset_x
+            && found.col + found.len <= self.viewport_offset_x + viewport_width;
+        if !visible {
+            let target_y = found.row.saturating_sub(viewport_height / 3);
+            let target_x = if found.col + found.len <= viewport_width {
+                0
+            } else {
+                found.col.saturating_sub(viewport_width / 4)
+            };
+            self.scroll_viewport(
+                target_x as isize - self.viewport_offset_x as isize,
+                target_y as isize - self.viewport_offset_y as isize,
+            );
+        }
+
+        self.cursor_x = (found.col - self.viewport_offset_x) as u16;
+        self.cursor_y = (found.row - self.viewport_offset_y) as u16;
+    }
+
+    // Ask PDFium for every page containing the query; regex patterns can't be used here
+    fn search_document(&mut self) -> Result<()> {
+        let Some(search) = self.search.as_mut() else {
+            return Ok(());
+        };
+        if search.query.pattern.is_empty() {
+            return Ok(());
+        }
+        if search.query.regex {
+            search.message = Some("document search needs a literal phrase".to_string());
+            return Ok(());
+        }
+
+        let phrase = search.query.pattern.clone();
+        let hits = self.document.search(&phrase, search.query.case_sensitive, false)?;
+        if hits.is_empty() {
+            search.message = Some("no matches in document".to_string());
+            return Ok(());
+        }
+
+        self.search_results = Some(SearchResults {
+            phrase,
+            hits,
+            selected: 0,
+            scroll: 0,
+        });
+        Ok(())
+    }
+
+    fn handle_search_results_key(&mut self, key: KeyCode) -> Result<()> {
+        let rows = self.search_results_rows();
+        let Some(results) = self.search_results.as_mut() else {
+            return Ok(());
+        };
+        let last = results.hits.len().saturating_sub(1);
+
+        match key {
+            KeyCode::Esc => {
+                self.search_results = None;
+                return Ok(());
+            }
+            KeyCode::Enter => {
+                let hit = results.hits[results.selected].clone();
+                self.search_results = None;
+                return self.go_to_hit(&hit);
+            }
+            KeyCode::Up => results.selected = results.selected.saturating_sub(1),
+            KeyCode::Down => results.selected = (results.selected + 1).min(last),
+            KeyCode::PageUp => results.selected = results.selected.saturating_sub(rows),
+            KeyCode::PageDown => results.selected = (results.selected + rows).min(last),
+            KeyCode::Home => results.selected = 0,
+            KeyCode::End => results.selected = last,
+            _ => {}
+        }
+
+        // Keep the selection inside the visible window
+        if results.selected < results.scroll {
+            results.scroll = results.selected;
+        } else if results.selected >= results.scroll + rows {
+            results.scroll = results.selected + 1 - rows;
+        }
+        Ok(())
+    }
+
+    // Load the hit's page and bring its position on the grid into view
+    fn go_to_hit(&mut self, hit: &TextHit) -> Result<()> {
+        self.go_to_page(hit.page)?;
+
+        // The word the hit starts in finds it in either view; page coordinates are the fallback
+        let word = self.elements
+            .iter()
+            .find(|e| {
+                e.hpos <= hit.hpos && hit.hpos < e.hpos + e.width
+                    && e.vpos < hit.vpos + hit.height && hit.vpos < e.vpos + e.height
+            })
+            .and_then(|e| Some((e, self.grid.placements.iter().find(|p| p.id == e.id)?)));
+        let (row, col, len) = match word {
+            Some((element, placement)) => {
+                let offset = ((hit.hpos - element.hpos) / element.width.max(1.0) * placement.len as f32) as usize;
+                (placement.row, placement.col + offset.min(placement.len.saturating_sub(1)), 1)
+            }
+            None => {
+                // Grid rows follow baselines, which sit close to the bottom of the hit's box
+                let (origin_x, origin_y) = self.grid.origin;
+                let col = ((hit.hpos - origin_x).max(0.0) * self.grid.scale) as usize;
+                let row = ((hit.vpos + hit.height - origin_y).max(0.0) * self.grid.scale) as usize;
+                (row, col, ((hit.width * self.grid.scale).ceil() as usize).max(1))
+            }
+        };
+        self.reveal_grid_span(GridMatch {
+            row: row.min(self.grid.height.saturating_sub(1)),
+            col: col.min(self.grid.width.saturating_sub(1)),
+            len,
+        });
+
+        // Highlight what the prompt's query matches on the new page
+        self.refresh_search_matches();
+        Ok(())
+    }
+
+    // Replace the match under the cursor by editing the buffer, like typing would
+    fn replace_current_match(&mut self) -> Result<()> {
+        if self.edit_blocked() {
+            return Ok(());
+        }
+        let Some(search) = self.search.as_ref() else {
+            return Ok(());
+        };
+        let Some(found) = search.current.and_then(|i| search.matches.get(i)).copied() else {
+            return self.find_next(true, true);
+        };
+        let Ok(re) = search.query.compile() else {
+            return Ok(());
+        };
+        let replacement = search.replacement.clone();
+
+        // Select the match so the replacement goes through the normal edit path (and undo);
+        // the cursor may have been moved off it, so position everything from the match itself
+        self.reveal_grid_span(found);
+        let row = (found.row - self.viewport_offset_y) as u16;
+        self.selection_anchor = Some(((found.col - self.viewport_offset_x) as u16, row));
+        self.cursor_x = (found.col + found.len - self.viewport_offset_x) as u16;
+        self.cursor_y = row;
+        let matched = self.selected_text().unwrap_or_default();
+        let text = re.replace(&matched, replacement.as_str()).into_owned();
+
+        if text.is_empty() {
+            self.record_edit();
+            self.delete_selection();
+        } else {
+            for c in text.chars() {
+                self.insert_char_at_cursor(c)?;
             }
+        }
 
-            // Additional viewport scrolling
-            KeyCode::PageUp => {
-                self.viewport_offset_y = self.viewport_offset_y.saturating_sub(10);
-            }
-            KeyCode::PageDown => {
-                self.viewport_offset_y = (self.viewport_offset_y + 10).min(self.grid_height.saturating_sub(20));
-            }
+        self.find_next(true, true)
+    }
 
-            // Mac-specific file operations
-            KeyCode::Char('s') | KeyCode::Char('S') if self.is_mac_modifier(normalized_modifiers) => {
-                self.save_extracted_text()?;
-            }
-            KeyCode::Char('o') | KeyCode::Char('O') if self.is_mac_modifier(normalized_modifiers) => {
-                // TODO: Implement file picker for opening new PDFs
-                // For now, just save session state
-                let _ = self.save_session_state();
-            }
-            KeyCode::Char('w') | KeyCode::Char('W') if self.is_mac_modifier(normalized_modifiers) => {
-                // Mac convention: Cmd+W closes window, Cmd+Q quits app
-                return Ok(true);
+    // Replace inside each element's text; matches that span words are left alone
+    fn replace_all_on_page(&mut self) {
+        if self.edit_blocked() {
+            return;
+        }
+        let Some(search) = self.search.as_ref() else {
+            return;
+        };
+        let Ok(re) = search.query.compile() else {
+            return;
+        };
+        let replacement = search.replacement.clone();
+
+        let mut replaced = 0;
+        let mut edited = self.elements.clone();
+        for element in &mut edited {
+            let count = re.find_iter(&element.content).filter(|m| !m.is_empty()).count();
+            if count > 0 {
+                replaced += count;
+                element.content = re.replace_all(&element.content, replacement.as_str()).into_owned();
             }
-            KeyCode::Char('q') | KeyCode::Char('Q') if self.is_mac_modifier(normalized_modifiers) => return Ok(true),
-            _ => {}
         }
-        
-        Ok(false)
+        edited.retain(|e| !e.content.trim().is_empty());
+
+        if replaced > 0 {
+            self.record_edit();
+            self.corrections.set(self.current_page, PageCorrections::diff(&self.original_elements, &edited));
+            self.elements = edited;
+            self.rebuild_text_buffer();
+        }
+
+        self.refresh_search_matches();
+        if let Some(search) = self.search.as_mut() {
+            search.message = Some(format!("replaced {}", replaced));
+        }
     }
-    
+
     fn insert_char_at_cursor(&mut self, c: char) -> Result<()> {
-        // Clear selection when editing
-        self.clear_selection();
+        if self.edit_blocked() {
+            return Ok(());
+        }
+
+        // Consecutive typing undoes as one step
+        let continues_run = self.selection_range().is_none()
+            && self.typing_run_end == Some((self.cursor_x, self.cursor_y));
+        if !continues_run {
+            self.record_edit();
+        }
+
+        // Typing replaces the selection
+        self.delete_selection();
 
         let mut lines: Vec<String> = self.text_buffer.lines().map(|s| s.to_string()).collect();
 
@@ -1004,35 +2385,40 @@ impl WysiwygEditor {
             }
         };
 
-        if c == '\n' {
-            // Split line at cursor - UTF-8 safe
-            let remaining = if safe_cursor_pos < current_line.len() {
-                current_line.split_off(safe_cursor_pos)
-            } else {
-                String::new()
-            };
-            lines.insert(self.cursor_y as usize + 1, remaining);
-            self.cursor_y += 1;
-            self.cursor_x = 0;
-        } else {
-            // Insert character at safe UTF-8 position
-            let insert_pos = safe_cursor_pos.min(current_line.len());
-            current_line.insert(insert_pos, c);
-            self.cursor_x += 1;
-        }
+        self.track_insert(self.cursor_x as usize, self.cursor_y as usize);
+
+        // Insert character at safe UTF-8 position
+        let insert_pos = safe_cursor_pos.min(current_line.len());
+        current_line.insert(insert_pos, c);
+        self.cursor_x += 1;
 
-        // Rebuild text buffer
+        // Rebuild text buffer and map the edit onto the elements
         self.text_buffer = lines.join("\n");
+        self.sync_elements_from_buffer();
+        self.typing_run_end = Some((self.cursor_x, self.cursor_y));
         Ok(())
     }
     
     fn delete_char_at_cursor(&mut self) -> Result<()> {
-        // Clear selection when editing
-        self.clear_selection();
+        if self.edit_blocked() {
+            return Ok(());
+        }
+        // Only record when something is removed; past the end of a line backspace just moves left
+        let line_len = self.text_buffer.lines().nth(self.cursor_y as usize).map_or(0, |line| line.chars().count());
+        let removes_char = self.cursor_x > 0 && (self.cursor_x as usize) <= line_len;
+        if removes_char || self.selection_range().is_some() {
+            self.record_edit();
+        }
+
+        // Backspace over a selection removes just the selection
+        if self.delete_selection() {
+            return Ok(());
+        }
 
         if self.cursor_x > 0 {
             let lines: Vec<&str> = self.text_buffer.lines().collect();
             let mut new_buffer = String::new();
+            let mut removed_at = None;
             
             for (i, line) in lines.iter().enumerate() {
                 if i == self.cursor_y as usize {
@@ -1040,6 +2426,7 @@ impl WysiwygEditor {
                     let cursor_pos = ((self.cursor_x - 1) as usize).min(line_chars.len());
                     if cursor_pos < line_chars.len() {
                         line_chars.remove(cursor_pos);
+                        removed_at = Some(cursor_pos);
                     }
                     new_buffer.push_str(&line_chars.iter().collect::<String>());
                     self.cursor_x -= 1;
@@ -1053,51 +2440,477 @@ impl WysiwygEditor {
             }
             
             self.text_buffer = new_buffer;
+
+            if let Some(col) = removed_at {
+                self.track_delete(col, self.cursor_y as usize);
+                self.sync_elements_from_buffer();
+            }
         }
         Ok(())
     }
 
+    // Keep grid placements aligned with the buffer after inserting a char at (col, row)
+    fn track_insert(&mut self, col: usize, row: usize) {
+        let grid_row = row + self.viewport_offset_y;
+        let grid_col = col + self.viewport_offset_x;
+
+        for placement in self.grid.placements.iter_mut().filter(|p| p.in_buffer) {
+            if placement.row == grid_row {
+                if placement.col >= grid_col {
+                    placement.col += 1;
+                } else if placement.col + placement.len > grid_col {
+                    placement.len += 1;
+                }
+            }
+        }
+    }
+
+    // Keep grid placements aligned with the buffer after removing the char at (col, row)
+    fn track_delete(&mut self, col: usize, row: usize) {
+        let grid_row = row + self.viewport_offset_y;
+        let grid_col = col + self.viewport_offset_x;
+
+        for placement in self.grid.placements.iter_mut().filter(|p| p.in_buffer) {
+            if placement.row == grid_row {
+                if placement.col > grid_col {
+                    placement.col -= 1;
+                } else if placement.col + placement.len > grid_col {
+                    placement.len -= 1;
+                }
+            }
+        }
+    }
+
+    fn edit_snapshot(&self) -> EditSnapshot {
+        EditSnapshot {
+            corrections: self.corrections.get(self.current_page).cloned().unwrap_or_default(),
+            position: self.cursor_grid_position(),
+        }
+    }
+
+    // Push the pre-edit state onto the page's undo stack
+    fn record_edit(&mut self) {
+        let snapshot = self.edit_snapshot();
+        self.corrections.history_mut(self.current_page).record(snapshot);
+        self.typing_run_end = None;
+    }
+
+    fn undo(&mut self) {
+        if self.edit_blocked() {
+            return;
+        }
+        let current = self.edit_snapshot();
+        if let Some(previous) = self.corrections.history_mut(self.current_page).undo(current) {
+            self.restore_snapshot(previous);
+        }
+    }
+
+    fn redo(&mut self) {
+        if self.edit_blocked() {
+            return;
+        }
+        let current = self.edit_snapshot();
+        if let Some(next) = self.corrections.history_mut(self.current_page).redo(current) {
+            self.restore_snapshot(next);
+        }
+    }
+
+    // Re-derive the page from a snapshot's corrections, the same way load_page does
+    fn restore_snapshot(&mut self, snapshot: EditSnapshot) {
+        self.elements = snapshot.corrections.apply(&self.original_elements);
+        self.corrections.set(self.current_page, snapshot.corrections);
+        self.rebuild_text_buffer();
+
+        // The cursor was saved as a grid position; scroll back to it if the view has moved since
+        let (row, col) = snapshot.position;
+        self.reveal_grid_span(GridMatch { row, col, len: 1 });
+        self.clear_selection();
+        self.typing_run_end = None;
+    }
+
+    // Keep grid placements aligned after the line below (col, row) is joined onto it at col
+    fn track_join(&mut self, col: usize, row: usize) {
+        let grid_row = row + self.viewport_offset_y;
+
+        for placement in self.grid.placements.iter_mut().filter(|p| p.in_buffer) {
+            if placement.row == grid_row + 1 {
+                placement.row = grid_row;
+                placement.col += col;
+            } else if placement.row > grid_row + 1 {
+                placement.row -= 1;
+            }
+        }
+    }
+
+    // Map the edited buffer back onto elements and record the page's corrections
+    fn sync_elements_from_buffer(&mut self) {
+        let (offset_x, offset_y) = (self.viewport_offset_x, self.viewport_offset_y);
+
+        // Whitespace-separated words of the buffer in grid coordinates
+        let mut tokens: Vec<(usize, usize, usize, String)> = Vec::new();
+        for (row, line) in self.text_buffer.lines().enumerate() {
+            let mut start = None;
+            for (col, ch) in line.chars().chain(std::iter::once(' ')).enumerate() {
+                match (ch == ' ', start) {
+                    (false, None) => start = Some(col),
+                    (true, Some(begin)) => {
+                        let text: String = line.chars()
+                            .skip(begin)
+                            .take(col - begin)
+                            .filter(|c| *c != '\u{200B}')
+                            .collect();
+                        tokens.push((row + offset_y, begin + offset_x, col + offset_x, text));
+                        start = None;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut content: HashMap<String, String> = HashMap::new();
+        let mut consumed: HashSet<String> = HashSet::new();
+        let mut skipped: HashSet<String> = HashSet::new();
+        let mut new_placements: Vec<GridPlacement> = Vec::new();
+        let mut inserted: Vec<(usize, usize, usize, String)> = Vec::new();
+
+        for (row, start, end, text) in tokens {
+            let overlapping: Vec<&GridPlacement> = self.grid.placements
+                .iter()
+                .filter(|p| p.row == row && p.len > 0 && p.col < end && start < p.col + p.len)
+                .collect();
+
+            // Words straddling the viewport edge are only partially in the buffer; leave
+            // everything under such a token as it was rather than reading it as deleted
+            if overlapping.iter().any(|p| !p.in_buffer) {
+                skipped.extend(overlapping.iter().map(|p| p.id.clone()));
+                continue;
+            }
+
+            let mut owner = None;
+            for placement in overlapping {
+                if consumed.insert(placement.id.clone()) && owner.is_none() {
+                    owner = Some(placement.id.clone());
+                }
+            }
+
+            match owner {
+                Some(id) => {
+                    content.insert(id.clone(), text);
+                    new_placements.push(GridPlacement { id, row, col: start, len: end - start, in_buffer: true });
+                }
+                None => inserted.push((row, start, end, text)),
+            }
+        }
+
+        // Visible words with no remaining text in the buffer were deleted
+        let removed: HashSet<String> = self.grid.placements
+            .iter()
+            .filter(|p| p.in_buffer && !content.contains_key(&p.id) && !skipped.contains(&p.id))
+            .map(|p| p.id.clone())
+            .collect();
+
+        self.elements.retain(|e| !removed.contains(&e.id));
+        for element in &mut self.elements {
+            if let Some(text) = content.remove(&element.id) {
+                element.content = text;
+            }
+        }
+
+        // Placements outside the buffer, or under a skipped token, stay as they were
+        let mut placements: Vec<GridPlacement> = self.grid.placements
+            .drain(..)
+            .filter(|p| (!p.in_buffer || skipped.contains(&p.id)) && !consumed.contains(&p.id))
+            .collect();
+        placements.extend(new_placements);
+
+        let line_height = if self.elements.is_empty() {
+            12.0
+        } else {
+            self.elements.iter().map(|e| e.height).sum::<f32>() / self.elements.len() as f32
+        };
+        let mut used_ids: HashSet<String> = self.elements.iter().map(|e| e.id.clone()).collect();
+        let mut next_id = 0;
+
+        for (row, start, end, text) in inserted {
+            let id = loop {
+                let candidate = format!("ins_{}", next_id);
+                next_id += 1;
+                if used_ids.insert(candidate.clone()) {
+                    break candidate;
+                }
+            };
+
+            // Typed words join the line of a word already on their row, so relayout keeps them there
+            let neighbour = placements
+                .iter()
+                .filter(|p| p.row == row)
+                .find_map(|p| self.elements.iter().find(|e| e.id == p.id));
+            // Otherwise count rows down from the nearest line above, since wrapped lines add rows
+            let above = placements
+                .iter()
+                .filter(|p| p.row < row)
+                .max_by_key(|p| p.row)
+                .and_then(|p| Some((p.row, self.elements.iter().find(|e| e.id == p.id)?)));
+            let (vpos, height, baseline) = match (neighbour, above) {
+                (Some(e), _) => (e.vpos, e.height, e.baseline()),
+                (None, Some((above_row, e))) => {
+                    let baseline = e.baseline() + (row - above_row) as f32 / self.grid.scale;
+                    (baseline - line_height, line_height, baseline)
+                }
+                (None, None) => {
+                    // Rows are laid out by baseline; keep the box from reaching above the grid's origin
+                    let baseline = self.grid.origin.1 + (row as f32 + 0.5) / self.grid.scale;
+                    let vpos = (baseline - line_height).max(self.grid.origin.1);
+                    (vpos, baseline - vpos, baseline)
+                }
+            };
+
+            let mut element = AltoElement::new(
+                id.clone(),
+                text,
+                self.grid.origin.0 + start as f32 / self.grid.scale,
+                vpos,
+                (end - start) as f32 / self.grid.scale,
+                height,
+            );
+            element.baseline = Some(baseline);
+            self.elements.push(element);
+            placements.push(GridPlacement { id, row, col: start, len: end - start, in_buffer: true });
+        }
+
+        self.grid.placements = placements;
+
+        let corrections = PageCorrections::diff(&self.original_elements, &self.elements);
+        self.corrections.set(self.current_page, corrections);
+    }
+
     fn select_all(&mut self) {
         // Select all text content
-        self.is_all_selected = true;
+        let lines: Vec<&str> = self.text_buffer.lines().collect();
+        self.selection_anchor = Some((0, 0));
+        self.cursor_y = lines.len().saturating_sub(1) as u16;
+        self.cursor_x = lines.last().map_or(0, |line| line.chars().count()) as u16;
     }
 
     fn clear_selection(&mut self) {
         // Clear any selection
-        self.is_all_selected = false;
+        self.selection_anchor = None;
+        self.block_selection = false;
+    }
+
+    fn select_word_at_cursor(&mut self) {
+        let lines: Vec<&str> = self.text_buffer.lines().collect();
+        let Some(line) = lines.get(self.cursor_y as usize) else {
+            return;
+        };
+        let chars: Vec<char> = line.chars().collect();
+        let col = self.cursor_x as usize;
+        if col >= chars.len() || chars[col] == ' ' {
+            return;
+        }
+
+        let start = chars[..col].iter().rposition(|c| *c == ' ').map_or(0, |i| i + 1);
+        let end = chars[col..].iter().position(|c| *c == ' ').map_or(chars.len(), |i| col + i);
+        self.selection_anchor = Some((start as u16, self.cursor_y));
+        self.cursor_x = end as u16;
+    }
+
+    fn select_line_at_cursor(&mut self) {
+        let lines: Vec<&str> = self.text_buffer.lines().collect();
+        let len = lines.get(self.cursor_y as usize).map_or(0, |line| line.chars().count());
+        self.selection_anchor = Some((0, self.cursor_y));
+        self.cursor_x = len as u16;
+    }
+
+    // Ordered ((row, col), (row, col)) bounds of the selection, end exclusive
+    fn selection_range(&self) -> Option<((usize, usize), (usize, usize))> {
+        let (anchor_x, anchor_y) = self.selection_anchor?;
+        let anchor = (anchor_y as usize, anchor_x as usize);
+        let cursor = (self.cursor_y as usize, self.cursor_x as usize);
+
+        match anchor.cmp(&cursor) {
+            std::cmp::Ordering::Less => Some((anchor, cursor)),
+            std::cmp::Ordering::Greater => Some((cursor, anchor)),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    // Left and right columns of a block, end exclusive; a block's corners can be in any order
+    fn block_columns(&self) -> Option<(usize, usize)> {
+        self.selection_range()?;
+        let (anchor_x, _) = self.selection_anchor?;
+        let (left, right) = (anchor_x.min(self.cursor_x), anchor_x.max(self.cursor_x));
+        Some((left as usize, right as usize))
+    }
+
+    // Selected columns of one buffer row, end exclusive
+    fn selection_span(&self, row: usize) -> Option<(usize, usize)> {
+        let ((start_row, start_col), (end_row, end_col)) = self.selection_range()?;
+        if row < start_row || row > end_row {
+            return None;
+        }
+
+        if self.block_selection {
+            self.block_columns()
+        } else {
+            let from = if row == start_row { start_col } else { 0 };
+            let to = if row == end_row { end_col } else { usize::MAX };
+            Some((from, to))
+        }
+    }
+
+    // Selected text of each row, with wide-char markers dropped
+    fn selected_rows(&self) -> Option<Vec<String>> {
+        let ((start_row, _), (end_row, _)) = self.selection_range()?;
+        let lines: Vec<&str> = self.text_buffer.lines().collect();
+
+        let rows = (start_row..=end_row)
+            .map(|row| {
+                let line = lines.get(row).copied().unwrap_or("");
+                let (from, to) = self.selection_span(row).unwrap_or((0, 0));
+                let text: String = line.chars().take(to).skip(from).filter(|c| *c != '\u{200B}').collect();
+                if self.block_selection {
+                    text.trim_end().to_string()
+                } else {
+                    text
+                }
+            })
+            .collect();
+        Some(rows)
+    }
+
+    fn selected_text(&self) -> Option<String> {
+        self.selected_rows().map(|rows| rows.join("\n"))
+    }
+
+    // Block rows as TSV, with runs of two or more spaces taken as column gaps
+    fn selected_tsv(&self) -> Option<String> {
+        let rows = self.selected_rows()?;
+        let tsv: Vec<String> = rows
+            .iter()
+            .map(|row| {
+                row.trim()
+                    .split("  ")
+                    .map(str::trim)
+                    .filter(|cell| !cell.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("\t")
+            })
+            .collect();
+        Some(tsv.join("\n"))
+    }
+
+    // Blank out the block in place so the rest of the layout doesn't move
+    fn clear_block(&mut self) {
+        let mut lines: Vec<Vec<char>> = self.text_buffer.lines().map(|l| l.chars().collect()).collect();
+        for (row, line) in lines.iter_mut().enumerate() {
+            if let Some((from, to)) = self.selection_span(row) {
+                for ch in line.iter_mut().take(to).skip(from) {
+                    *ch = ' ';
+                }
+            }
+        }
+
+        if let (Some(((start_row, _), _)), Some((left, _))) = (self.selection_range(), self.block_columns()) {
+            self.cursor_x = left as u16;
+            self.cursor_y = start_row as u16;
+        }
+        self.clear_selection();
+
+        self.text_buffer = lines.iter().map(|l| l.iter().collect::<String>()).collect::<Vec<_>>().join("\n");
+        self.sync_elements_from_buffer();
+    }
+
+    // Remove the selected text, leaving the cursor at its start; false if nothing was selected
+    fn delete_selection(&mut self) -> bool {
+        let Some(((start_row, start_col), (end_row, end_col))) = self.selection_range() else {
+            self.clear_selection();
+            return false;
+        };
+        if self.block_selection {
+            self.clear_block();
+            return true;
+        }
+        self.clear_selection();
+
+        let mut lines: Vec<Vec<char>> = self.text_buffer.lines().map(|l| l.chars().collect()).collect();
+        if start_row >= lines.len() {
+            return false;
+        }
+        let end_row = end_row.min(lines.len() - 1);
+        let start_col = start_col.min(lines[start_row].len());
+        let end_col = end_col.min(lines[end_row].len());
+
+        // Track removals back to front so earlier positions stay valid
+        for row in (start_row..=end_row).rev() {
+            let from = if row == start_row { start_col } else { 0 };
+            let to = if row == end_row { end_col } else { lines[row].len() };
+            if row < end_row {
+                self.track_join(lines[row].len(), row);
+            }
+            for col in (from..to).rev() {
+                self.track_delete(col, row);
+            }
+        }
+
+        let tail: Vec<char> = lines[end_row][end_col..].to_vec();
+        lines[start_row].truncate(start_col);
+        lines[start_row].extend(tail);
+        lines.drain(start_row + 1..=end_row);
+
+        self.text_buffer = lines.iter().map(|l| l.iter().collect::<String>()).collect::<Vec<_>>().join("\n");
+        self.cursor_x = start_col as u16;
+        self.cursor_y = start_row as u16;
+        self.sync_elements_from_buffer();
+        true
     }
 
     fn copy_selection(&mut self) -> Result<()> {
-        if self.is_all_selected {
-            // Copy all text to internal clipboard
-            self.clipboard = self.text_buffer.clone();
+        if let Some(text) = self.selected_text() {
+            // Copy selected text to internal clipboard
+            self.clipboard = text;
 
             // Copy to system clipboard so other apps can access it
-            if let Ok(mut system_clipboard) = arboard::Clipboard::new() {
-                let _ = system_clipboard.set_text(&self.text_buffer);
-            }
+            self.copy_to_system_clipboard();
+        }
+        Ok(())
+    }
+
+    fn copy_to_system_clipboard(&mut self) {
+        let copied = arboard::Clipboard::new().and_then(|mut system| system.set_text(&self.clipboard));
+        match copied {
+            Ok(()) => self.notify(MessageLevel::Info, format!("Copied {} characters", self.clipboard.chars().count())),
+            Err(e) => self.notify(MessageLevel::Warning, format!("Copied inside chonker95 only, system clipboard failed: {}", e)),
+        }
+    }
+
+    fn copy_selection_as_tsv(&mut self) -> Result<()> {
+        if let Some(text) = self.selected_tsv() {
+            self.clipboard = text;
+            self.copy_to_system_clipboard();
         }
         Ok(())
     }
 
     fn cut_selection(&mut self) -> Result<()> {
-        if self.is_all_selected {
+        if self.edit_blocked() {
+            return Ok(());
+        }
+        if self.selection_range().is_some() {
             // Copy to clipboard first
             self.copy_selection()?;
+            self.record_edit();
 
-            // Then clear all text
-            self.text_buffer = String::new();
-            self.cursor_x = 0;
-            self.cursor_y = 0;
-            self.clear_selection();
+            // Then remove the selected text
+            self.delete_selection();
         }
         Ok(())
     }
 
     fn paste_from_clipboard(&mut self) -> Result<()> {
-        // Clear selection when pasting
-        self.clear_selection();
-
+        if self.edit_blocked() {
+            return Ok(());
+        }
         // Try to get text from system clipboard first, fallback to internal
         let clipboard_text = if let Ok(mut system_clipboard) = arboard::Clipboard::new() {
             system_clipboard.get_text().unwrap_or_else(|_| self.clipboard.clone())
@@ -1105,6 +2918,18 @@ impl WysiwygEditor {
             self.clipboard.clone()
         };
 
+        // Words keep their page positions, so pasted lines are joined onto the cursor's line
+        let clipboard_text = clipboard_text.lines().collect::<Vec<_>>().join(" ");
+
+        // Nothing to paste and nothing to replace leaves no undo step
+        if clipboard_text.is_empty() && self.selection_range().is_none() {
+            return Ok(());
+        }
+        self.record_edit();
+
+        // Pasting replaces the selection
+        self.delete_selection();
+
         if !clipboard_text.is_empty() {
             // Insert clipboard content at cursor position
             let lines: Vec<String> = self.text_buffer.lines().map(|s| s.to_string()).collect();
@@ -1118,71 +2943,29 @@ impl WysiwygEditor {
             // Get current line and split at cursor - SAFE UTF-8 handling
             let current_line = &mut new_lines[self.cursor_y as usize];
 
-            // Convert cursor position to safe byte index for UTF-8
-            let safe_cursor_pos = {
-                let mut char_count = 0;
-                let mut byte_pos = 0;
-                for (i, _) in current_line.char_indices() {
-                    if char_count >= self.cursor_x as usize {
-                        break;
-                    }
-                    byte_pos = i;
-                    char_count += 1;
-                }
-                // If cursor is beyond string, use string length
-                if char_count < self.cursor_x as usize {
-                    current_line.len()
-                } else {
-                    byte_pos
-                }
-            };
-
             // Extend line with spaces if cursor is beyond current text
             while current_line.chars().count() < self.cursor_x as usize {
                 current_line.push(' ');
             }
 
-            // Insert clipboard content
-            let clipboard_lines: Vec<&str> = clipboard_text.lines().collect();
-
-            if clipboard_lines.len() <= 1 {
-                // Single line paste - insert at safe cursor position
-                let insert_pos = safe_cursor_pos.min(current_line.len());
-                current_line.insert_str(insert_pos, &clipboard_lines.get(0).unwrap_or(&""));
-                self.cursor_x += clipboard_lines.get(0).unwrap_or(&"").chars().count() as u16;
-            } else {
-                // Multi-line paste - split current line safely
-                let safe_split_pos = safe_cursor_pos.min(current_line.len());
-                let remaining_text = if safe_split_pos < current_line.len() {
-                    current_line.split_off(safe_split_pos)
-                } else {
-                    String::new()
-                };
-
-                current_line.push_str(&clipboard_lines[0]);
-
-                // Insert middle lines
-                if clipboard_lines.len() > 2 {
-                    for (i, line) in clipboard_lines[1..clipboard_lines.len()-1].iter().enumerate() {
-                        new_lines.insert(self.cursor_y as usize + i + 1, line.to_string());
-                    }
-                }
-
-                // Insert last line with remaining text
-                let last_line = format!("{}{}",
-                    clipboard_lines.last().unwrap_or(&""),
-                    remaining_text
-                );
-                new_lines.insert(
-                    self.cursor_y as usize + clipboard_lines.len().saturating_sub(1),
-                    last_line
-                );
-
-                self.cursor_y += clipboard_lines.len().saturating_sub(1) as u16;
-                self.cursor_x = clipboard_lines.last().unwrap_or(&"").chars().count() as u16;
+            // Convert cursor position to safe byte index for UTF-8
+            let safe_cursor_pos = current_line
+                .char_indices()
+                .nth(self.cursor_x as usize)
+                .map(|(i, _)| i)
+                .unwrap_or(current_line.len());
+
+            // Track each pasted char so element spans follow the inserted text
+            for offset in 0..clipboard_text.chars().count() {
+                self.track_insert(self.cursor_x as usize + offset, self.cursor_y as usize);
             }
 
+            let insert_pos = safe_cursor_pos.min(current_line.len());
+            current_line.insert_str(insert_pos, &clipboard_text);
+            self.cursor_x += clipboard_text.chars().count() as u16;
+
             self.text_buffer = new_lines.join("\n");
+            self.sync_elements_from_buffer();
         }
 
         Ok(())
@@ -1274,8 +3057,100 @@ fn cleanup_terminal(terminal_info: &TerminalInfo) -> Result<()> {
     Ok(())
 }
 
+// Run a headless subcommand, writing results to stdout or a file
+fn run_batch(command: Command) -> Result<()> {
+    let args = match &command {
+        Command::Extract(args) | Command::Render(args) | Command::Export(args) => args,
+    };
+
+    let config = Config::load()?;
+    let pdfium = Pdfium::default();
+    let document = Document::open(&pdfium, &args.file)?.with_layout(config.layout);
+
+    let page_numbers = match &args.pages {
+        Some(spec) => parse_page_ranges(spec, document.page_count())?,
+        None => (1..=document.page_count()).collect(),
+    };
+
+    let corrections = if args.raw { None } else { Some(CorrectionStore::open(&args.file)?) };
+
+    let mut pages = Vec::new();
+    for number in page_numbers {
+        let mut page = document.page(number)?;
+        if let Some(page_corrections) = corrections.as_ref().and_then(|c| c.get(number)) {
+            let unmatched = page_corrections.unmatched(&page.elements);
+            if unmatched > 0 {
+                log::warn!("page {}: skipped {} corrections that no longer match the extracted words", number, unmatched);
+            }
+            page.elements = page_corrections.apply(&page.elements);
+        }
+        pages.push(page);
+    }
+
+    let output = match command {
+        Command::Extract(_) => serde_json::to_string_pretty(&pages)? + "\n",
+        Command::Render(_) => {
+            // Pages are separated by form feeds, like pdftotext
+            let rendered: Vec<String> = pages
+                .iter()
+                .map(|page| {
+                    let grid = SpatialGrid::layout(&page.elements, &config.layout);
+                    if grid.displaced > 0 {
+                        log::info!("page {}: {} words moved to avoid overlapping text", page.number, grid.displaced);
+                    }
+                    grid.to_text() + "\n"
+                })
+                .collect();
+            rendered.join("\x0c")
+        }
+        Command::Export(_) => AltoWriter::write_document(&args.file, &pages),
+    };
+
+    match &args.output {
+        Some(path) => std::fs::write(path, output)?,
+        None => io::stdout().lock().write_all(output.as_bytes())?,
+    }
+
+    Ok(())
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    logging::init(cli.log_level.as_deref())?;
+
+    // Subcommands run headless, without raw mode or the alternate screen
+    if let Some(command) = cli.command {
+        let result = run_batch(command);
+        if let Err(e) = &result {
+            log::error!("batch command failed: {:#}", e);
+        }
+        return result;
+    }
+
+    // Without a file argument we always resume the most recent session
+    let sessions = SessionState::load();
+    let (pdf_path, resume_entry) = match cli.file {
+        Some(file) => {
+            let entry = if cli.resume { sessions.find(&file).cloned() } else { None };
+            (file, entry)
+        }
+        None => {
+            let entry = sessions.last()
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("No PDF file given and no previous session to resume"))?;
+            (entry.file.clone(), Some(entry))
+        }
+    };
+    let page = cli.page
+        .or(resume_entry.as_ref().map(|entry| entry.page))
+        .unwrap_or(1);
+
+    // Config problems are reported before the terminal switches to raw mode
+    let config = EditorConfig::load()?;
+    let default_mode = config.default_mode;
+
+    // Bind PDFium once; the editor keeps the document open for the whole session
+    let pdfium = Pdfium::default();
 
     // Detect terminal early for proper setup
     let terminal_info = TerminalInfo::detect();
@@ -1293,6 +3168,7 @@ fn main() -> Result<()> {
         );
         let _ = terminal::disable_raw_mode();
 
+        log::error!("panic: {}", panic_info);
         eprintln!("PANIC occurred: {}", panic_info);
         eprintln!("Terminal has been restored to normal mode.");
         eprintln!("This was likely caused by invalid UTF-8 string operations.");
@@ -1306,60 +3182,100 @@ fn main() -> Result<()> {
         return Err(e);
     }
 
-    let result = {
-        let mut editor = WysiwygEditor::new(cli.file, cli.page)?;
+    // Failures while saving on quit, reported once the terminal is back to normal
+    let mut save_errors: Vec<String> = Vec::new();
+
+    // A closure, so an early error still reaches the terminal cleanup below
+    let result = (|| -> Result<()> {
+        let viewport_limits = ViewportLimits {
+            max_cols: cli.max_cols,
+            max_rows: cli.max_rows,
+        };
+        let mut editor = WysiwygEditor::new(&pdfium, pdf_path, page, viewport_limits, config)?;
+        match &resume_entry {
+            Some(entry) => editor.restore_session(entry)?,
+            None if default_mode == DisplayMode::SplitScreen && WysiwygEditor::is_in_zellij() => {
+                editor.open_viuer_pane()?
+            }
+            None if default_mode == DisplayMode::Reflow => editor.toggle_reflow()?,
+            None => {}
+        }
 
         loop {
             match editor.render() {
                 Ok(()) => {},
                 Err(e) => {
-                    eprintln!("Render error: {}", e);
+                    editor.notify(MessageLevel::Error, format!("Render error: {}", e));
                     editor.terminal_state_clean = false;
                     continue;
                 }
             }
 
+            // Wake up now and then so status messages expire without a keypress; while pages
+            // are queued for prefetch, only check for input and extract one between checks
+            let timeout = if editor.prefetcher.is_idle() { Duration::from_millis(500) } else { Duration::ZERO };
+            match event::poll(timeout) {
+                Ok(true) => {}
+                Ok(false) => {
+                    editor.prefetch_next();
+                    continue;
+                }
+                Err(e) => {
+                    editor.notify(MessageLevel::Error, format!("Event read error: {}", e));
+                    continue;
+                }
+            }
+
             match event::read() {
-                Ok(Event::Mouse(MouseEvent {
-                    kind: MouseEventKind::Down(MouseButton::Left),
-                    column: x,
-                    row: y,
-                    ..
-                })) => {
-                    if let Err(e) = editor.handle_mouse_click(x, y) {
-                        eprintln!("Mouse handling error: {}", e);
-                        editor.terminal_state_clean = false;
+                Ok(Event::Mouse(mouse_event)) => {
+                    if let Err(e) = editor.handle_mouse_event(mouse_event) {
+                        editor.notify(MessageLevel::Error, format!("Mouse handling error: {}", e));
                     }
                 }
                 Ok(Event::Key(key_event)) => {
                     match editor.handle_key_input(key_event.code, key_event.modifiers) {
                         Ok(true) => {
-                            // Save session state before quitting
-                            let _ = editor.save_session_state();
+                            // Save session state and corrections before quitting; edits are only
+                            // written on page changes and here, so a failure must not pass silently
+                            if let Err(e) = editor.save_session_state() {
+                                log::error!("couldn't save the session: {}", e);
+                                save_errors.push(format!("Couldn't save the session: {}", e));
+                            }
+                            if let Err(e) = editor.corrections.save(&editor.pdf_path) {
+                                log::error!("couldn't save corrections for {}: {}", editor.pdf_path.display(), e);
+                                save_errors.push(format!("Couldn't save your corrections: {}", e));
+                            }
                             break; // Quit requested
                         }
                         Ok(false) => {}, // Continue
                         Err(e) => {
-                            eprintln!("Key handling error: {}", e);
-                            editor.terminal_state_clean = false;
+                            editor.notify(MessageLevel::Error, e.to_string());
                         }
                     }
                 }
+                Ok(Event::Resize(width, height)) => {
+                    if let Err(e) = editor.handle_resize(width, height) {
+                        editor.notify(MessageLevel::Error, format!("Resize handling error: {}", e));
+                    }
+                }
                 Ok(_) => {} // Other events
                 Err(e) => {
-                    eprintln!("Event read error: {}", e);
+                    editor.notify(MessageLevel::Error, format!("Event read error: {}", e));
                     editor.terminal_state_clean = false;
                 }
             }
         }
 
         Ok(())
-    };
+    })();
 
     // Always cleanup terminal, even on error
     if let Err(cleanup_err) = cleanup_terminal(&terminal_info) {
         eprintln!("Terminal cleanup failed: {}", cleanup_err);
     }
+    for error in &save_errors {
+        eprintln!("{}", error);
+    }
 
     result
 }
\ No newline at end of file
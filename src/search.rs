@@ -0,0 +1,76 @@
+use anyhow::Result;
+use regex::{Regex, RegexBuilder};
+use unicode_width::UnicodeWidthChar;
+
+use crate::layout::SpatialGrid;
+
+/// What to look for and how to match it
+#[derive(Debug, Clone, Default)]
+pub struct SearchQuery {
+    pub pattern: String,
+    /// Treat the pattern as a regular expression instead of literal text
+    pub regex: bool,
+    pub case_sensitive: bool,
+}
+
+impl SearchQuery {
+    pub fn compile(&self) -> Result<Regex> {
+        let pattern = if self.regex {
+            self.pattern.clone()
+        } else {
+            regex::escape(&self.pattern)
+        };
+
+        Ok(RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .build()?)
+    }
+}
+
+/// A match in grid cells; `len` counts display columns, not chars
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct GridMatch {
+    pub row: usize,
+    pub col: usize,
+    pub len: usize,
+}
+
+/// Find every match in the grid, row by row; matches never span rows
+pub fn find_in_grid(grid: &SpatialGrid, re: &Regex) -> Vec<GridMatch> {
+    let mut matches = Vec::new();
+
+    for (row, cells) in grid.cells.iter().enumerate() {
+        // Row text without wide-char markers, and the grid column of each byte offset
+        let mut text = String::new();
+        let mut columns = Vec::new();
+        for (col, ch) in cells.iter().enumerate() {
+            if *ch == '\u{200B}' {
+                continue;
+            }
+            columns.push((text.len(), col));
+            text.push(*ch);
+        }
+        let column_at = |offset: usize| match columns.binary_search_by_key(&offset, |(byte, _)| *byte) {
+            Ok(i) => columns[i].1,
+            Err(_) => grid.width,
+        };
+
+        for found in re.find_iter(&text) {
+            // Skip empty matches, which a regex like `a*` produces everywhere
+            if found.is_empty() {
+                continue;
+            }
+
+            let start = column_at(found.start());
+            let last = found.as_str().chars().last().unwrap_or(' ');
+            let end = column_at(found.end() - last.len_utf8()) + last.width().unwrap_or(1);
+            matches.push(GridMatch {
+                row,
+                col: start,
+                len: end - start,
+            });
+        }
+    }
+
+    matches
+}